@@ -1,13 +1,21 @@
-use actix_web::{body::BoxBody, http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{body::BoxBody, http::{header::RETRY_AFTER, StatusCode}, HttpResponse, ResponseError};
 use derive_more::derive::{Display, Error};
 use serde::{Serialize, Deserialize};
 
+pub const RETRY_AFTER_MS_HEADER: &str = "X-RETRY-AFTER-MS";
+
 #[derive(Display, Error, Debug)]
 pub enum ApiError{
-    #[display("Unauthorized")]
-    UnAuthorized,
+    #[display("Unauthorized : {_0}")]
+    UnAuthorized(#[error(not(source))] &'static str),
+    #[display("Forbidden : {_0}")]
+    Forbidden(#[error(not(source))] &'static str),
+    #[display("Too Many Requests : retry after {_0} ms")]
+    TooManyRequests(#[error(not(source))] i64),
     #[display("Internal Server Error")]
     InternalServerError,
+    #[display("Gateway Timeout : no response from the engine")]
+    GatewayTimeout,
 }
 
 #[derive(Display, Error, Debug, Serialize, Deserialize)]
@@ -21,6 +29,11 @@ impl CustomApiError{
         let err = CustomApiError { error: String::from("Internal Server Error") };
         HttpResponse::InternalServerError().json(err)
     }
+
+    pub fn bad_request(error:&str) -> HttpResponse<BoxBody>{
+        let err = CustomApiError { error: error.to_string() };
+        HttpResponse::BadRequest().json(err)
+    }
 }
 
 
@@ -28,13 +41,26 @@ impl ResponseError for CustomApiError{}
 
 impl ResponseError for ApiError{
     fn error_response(&self) -> actix_web::HttpResponse<actix_web::body::BoxBody> {
-        HttpResponse::build(self.status_code()).json(CustomApiError{error:self.to_string()})
+
+        let mut response = HttpResponse::build(self.status_code());
+
+        // Retry-After only has seconds, so the ms are sent too
+        if let ApiError::TooManyRequests(retry_after_ms) = *self {
+            response
+            .insert_header((RETRY_AFTER, ((retry_after_ms + 999) / 1000).to_string()))
+            .insert_header((RETRY_AFTER_MS_HEADER, retry_after_ms.to_string()));
+        }
+
+        response.json(CustomApiError{error:self.to_string()})
     }
 
     fn status_code(&self) -> actix_web::http::StatusCode {
         match *self{
-            ApiError::UnAuthorized => StatusCode::UNAUTHORIZED,
-            ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR
+            ApiError::UnAuthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::GatewayTimeout => StatusCode::GATEWAY_TIMEOUT,
         }
     }
 }
@@ -0,0 +1,134 @@
+use actix_web::{delete, get, post, web::{Data, Json, Path, ReqData}, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use store::ApiKey;
+
+use crate::{entrypoint::AppState, errors::{ApiError, CustomApiError}, middleware::auth::AuthenticatedUser, utils::api_key::{generate_api_key, get_allowed_ips, get_scopes, AllowedIpsError, ApiKeyScope}};
+
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateApiKey {
+    pub label: Option<String>,
+    pub scopes: Vec<ApiKeyScope>,
+    // the key can only be used from these ips, from any ip when not given
+    pub allowed_ips: Option<Vec<String>>,
+}
+
+/// the key and the secret are only shown once, they can't be fetched again
+#[derive(Serialize)]
+pub struct CreateApiKeyResponse {
+    api_key: String,
+    secret: String,
+    #[serde(flatten)]
+    key: ApiKey,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeysResponse {
+    api_keys: Vec<ApiKey>,
+}
+
+#[derive(Serialize)]
+pub struct ApiKeyRevokedResponse {
+    id: String,
+}
+
+/// a key can't have a scope which the key creating it doesn't have,
+/// so a read only key can't be used to get a withdrawal key, nor an ip the key isn't allowed from
+#[post("/api_key")]
+pub async fn create_api_key(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>, payload:Json<CreateApiKey>) -> HttpResponse {
+
+    let payload = payload.into_inner();
+
+    let label = payload.label.unwrap_or_default();
+
+    if label.chars().count() > MAX_LABEL_LEN {
+        return CustomApiError::bad_request(&format!("label can't be longer than {} characters", MAX_LABEL_LEN));
+    }
+
+    let scopes: Vec<ApiKeyScope> = ApiKeyScope::ALL.into_iter().filter(|scope| payload.scopes.contains(scope)).collect();
+
+    if scopes.is_empty() {
+        return CustomApiError::bad_request("api key needs at least one scope");
+    }
+
+    if let Some(scope) = scopes.iter().find(|scope| !user.scopes.contains(scope)) {
+        println!("api key : {} of user : {} can't create a key with the {} scope", user.key_id, user.user_id, scope.as_str());
+        return ApiError::Forbidden("api key can't give a scope it doesn't have").error_response();
+    }
+
+    let allowed_ips = match get_allowed_ips(payload.allowed_ips, &user.allowed_ips) {
+        Ok(allowed_ips) => allowed_ips,
+        Err(AllowedIpsError::Invalid(ip)) => return CustomApiError::bad_request(&format!("invalid ip address : {}", ip)),
+        Err(AllowedIpsError::NotAllowed) => {
+            println!("api key : {} of user : {} can't create a key allowed from other ips", user.key_id, user.user_id);
+            return ApiError::Forbidden("api key can't allow ips it isn't allowed from").error_response();
+        }
+    };
+
+    let (key, api_key) = generate_api_key(&user.user_id, label, &scopes, allowed_ips);
+
+    if let Err(e) = ApiKey::add_api_key(&api_key, &app_state.db_pool).await {
+        println!("error : {} while adding api key of user : {}", e, user.user_id);
+        return CustomApiError::internal_error();
+    }
+
+    println!("created api key : {} for user : {} with scopes : {:?}", api_key.id, user.user_id, api_key.scopes);
+
+    HttpResponse::Ok().json(CreateApiKeyResponse {
+        api_key: key,
+        secret: api_key.secret.clone(),
+        key: api_key,
+    })
+}
+
+#[get("/api_keys")]
+pub async fn get_api_keys(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>) -> HttpResponse {
+
+    if let Some(response) = user.check_scope(ApiKeyScope::Read) {
+        return response;
+    }
+
+    match ApiKey::get_user_api_keys(&user.user_id, &app_state.db_pool).await {
+        Ok(api_keys) => HttpResponse::Ok().json(ApiKeysResponse { api_keys }),
+        Err(e) => {
+            println!("error : {} while fetching api keys of user : {}", e, user.user_id);
+            CustomApiError::internal_error()
+        }
+    }
+}
+
+/// the key is deleted, so the requests signed with it are rejected right away.
+/// same as creating, a key can only revoke the keys whose scopes it has.
+#[delete("/api_key/{id}")]
+pub async fn revoke_api_key(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>, path:Path<String>) -> HttpResponse {
+
+    let id = path.into_inner();
+
+    let api_key = match ApiKey::get_api_key(&id, &user.user_id, &app_state.db_pool).await {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return CustomApiError::bad_request("api key not found"),
+        Err(e) => {
+            println!("error : {} while fetching api key : {}", e, id);
+            return CustomApiError::internal_error();
+        }
+    };
+
+    if get_scopes(&api_key).iter().any(|scope| !user.scopes.contains(scope)) {
+        println!("api key : {} of user : {} can't revoke the api key : {}", user.key_id, user.user_id, id);
+        return ApiError::Forbidden("api key can't revoke a key with a scope it doesn't have").error_response();
+    }
+
+    match ApiKey::delete_api_key(&id, &user.user_id, &app_state.db_pool).await {
+        Ok(true) => {
+            println!("revoked api key : {} of user : {}", id, user.user_id);
+            HttpResponse::Ok().json(ApiKeyRevokedResponse { id })
+        },
+        // revoked by another request in the meantime
+        Ok(false) => CustomApiError::bad_request("api key not found"),
+        Err(e) => {
+            println!("error : {} while revoking api key : {}", e, id);
+            CustomApiError::internal_error()
+        }
+    }
+}
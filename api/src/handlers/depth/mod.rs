@@ -1,53 +1,51 @@
 use std::time::Instant;
-use actix_web::{get, web::{Data, Path}, HttpResponse};
-use common::message::{api::MessageFromApi, engine::DepthResponse};
+use actix_web::{get, web::{Data, Path, Query}, HttpResponse};
+use common::{message::{api::{DepthPayload, MessageFromApi}, engine::DepthResponse}, types::order::Price};
+use serde::Deserialize;
+use uuid::Uuid;
 
-use crate::{entrypoint::AppState, errors::CustomApiError, services::redis::{PubSubService, RedisService}, utils::{engine_res_wrapper::get_engine_http_response, observer::Observer}};
+use crate::{entrypoint::AppState, errors::CustomApiError, utils::{engine_res_wrapper::get_engine_http_response, observer::Observer, pagination::{get_page_limit, MAX_PAGE_LIMIT}}};
+
+#[derive(Deserialize, Debug)]
+pub struct DepthQuery {
+    // price levels on each side
+    pub limit: Option<i64>,
+    // size of the price buckets the levels are merged into, eg 0.1
+    pub grouping: Option<Price>,
+}
 
 #[get("/depth/{market}")]
-pub async fn get_depth(app_state:Data<AppState>, path:Path<String> ) -> HttpResponse{
+pub async fn get_depth(app_state:Data<AppState>, path:Path<String>, query:Query<DepthQuery> ) -> HttpResponse{
 
     let now = Instant::now();
     let route = String::from("Depth");
     
     let observer = Observer::new(now, route);
 
-    let pool = &app_state.redis_pool;
-    let conn_1_res = pool.get();
-    let conn_2_res = pool.get();
-
-    let market = path.into_inner();
-
-    if let Err(e) = conn_1_res {
-        println!("error while getting redis connection from pool :{}",e);
-        return CustomApiError::internal_error();
-    }
+    let query = query.into_inner();
 
-    if let Err(e) = conn_2_res {
-        println!("error while getting redis connection from pool :{} ",e);
-        return CustomApiError::internal_error();
-    }
+    let limit = match get_page_limit(query.limit) {
+        Some(limit) => limit as usize,
+        None => return CustomApiError::bad_request(&format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+    };
 
-    let conn_1 = conn_1_res.unwrap();
-    let mut conn_2 = conn_2_res.unwrap();
-
-    let mut redis_service = RedisService::new(conn_1);
-    
-    let channel_to_publish = market.clone();
-    let pub_sub = conn_2.as_pubsub();
+    let market = path.into_inner();
 
-    let mut pub_sub_service = PubSubService::new(
-        pub_sub, 
-        &channel_to_publish
-    );
+    // every request gets its own channel, so concurrent
+    // requests with different limits don't mix up
+    let channel_to_publish = Uuid::new_v4().to_string();
 
-    let message_from_api = MessageFromApi::GetDepth(market);
+    let message_from_api = MessageFromApi::GetDepth(DepthPayload {
+        id: channel_to_publish.clone(),
+        market,
+        limit: Some(limit),
+        grouping: query.grouping,
+    });
 
     get_engine_http_response::<DepthResponse>(
-        message_from_api, 
-        &mut redis_service, 
-        &mut pub_sub_service,
+        message_from_api,
+        &app_state.engine,
         observer
-    )
+    ).await
 
-}
\ No newline at end of file
+}
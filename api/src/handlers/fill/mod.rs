@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+use actix_web::{get, web::{Data, Query, ReqData}, HttpResponse};
+use common::types::order::OrderSide;
+use serde::{Deserialize, Serialize};
+use store::{Fill, FillCursor, FillFilter};
+
+use crate::{entrypoint::AppState, errors::CustomApiError, middleware::auth::AuthenticatedUser, utils::{api_key::ApiKeyScope, pagination::{get_page_limit, MAX_PAGE_LIMIT}}};
+
+#[derive(Deserialize, Debug)]
+pub struct FillsQuery {
+    pub market: Option<String>,
+    pub side: Option<OrderSide>,
+    // milliseconds, both inclusive
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<i64>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FillsResponse {
+    fills: Vec<Fill>,
+    next_cursor: Option<String>,
+}
+
+#[get("/fills")]
+pub async fn get_fills(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>, query:Query<FillsQuery>) -> HttpResponse {
+
+    if let Some(response) = user.check_scope(ApiKeyScope::Read) {
+        return response;
+    }
+
+    let start_time = Instant::now();
+
+    let query = query.into_inner();
+
+    let limit = match get_page_limit(query.limit) {
+        Some(limit) => limit,
+        None => return CustomApiError::bad_request(&format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+    };
+
+    let cursor = match query.cursor {
+        Some(cursor) => match FillCursor::decode(&cursor) {
+            Some(cursor) => Some(cursor),
+            None => return CustomApiError::bad_request("invalid cursor"),
+        },
+        None => None,
+    };
+
+    let filter = FillFilter {
+        user_id: user.into_inner().user_id,
+        market: query.market,
+        side: query.side.map(|side| side.to_string()),
+        start_time: query.start_time,
+        end_time: query.end_time,
+        cursor,
+        limit,
+    };
+
+    let try_fills = Fill::get_fills(filter, &app_state.db_pool).await;
+
+    let elapsed = start_time.elapsed();
+
+    println!("Fills route completed in: {}.{} ms", elapsed.as_millis(), elapsed.subsec_micros());
+
+    match try_fills {
+        Ok((fills, next_cursor)) => {
+            HttpResponse::Ok().json(FillsResponse {
+                fills,
+                next_cursor: next_cursor.map(|cursor| cursor.encode()),
+            })
+        },
+        Err(e) => {
+            println!("error : {} while fetching fills", e);
+            CustomApiError::internal_error()
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Instant;
+
+use actix_web::{get, web::{Data, Path, Query}, HttpResponse};
+use common::types::kline::KlineInterval;
+use serde::{Deserialize, Serialize};
+use store::{Kline, KlineFilter};
+
+use crate::{entrypoint::AppState, errors::CustomApiError, utils::pagination::{get_page_limit, MAX_PAGE_LIMIT}};
+
+#[derive(Deserialize, Debug)]
+pub struct KlinesQuery {
+    pub interval: KlineInterval,
+    // open times in milliseconds, both inclusive
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct KlinesResponse {
+    klines: Vec<Kline>,
+}
+
+#[get("/klines/{market}")]
+pub async fn get_klines(app_state:Data<AppState>, path:Path<String>, query:Query<KlinesQuery>) -> HttpResponse {
+
+    let start_time = Instant::now();
+
+    let market = path.into_inner();
+    let query = query.into_inner();
+
+    if app_state.market_registry.get(&market).is_none() {
+        return CustomApiError::bad_request("invalid market");
+    }
+
+    let limit = match get_page_limit(query.limit) {
+        Some(limit) => limit,
+        None => return CustomApiError::bad_request(&format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+    };
+
+    let filter = KlineFilter {
+        market,
+        interval: query.interval.to_string(),
+        start_time: query.start_time,
+        end_time: query.end_time,
+        limit,
+    };
+
+    let try_klines = Kline::get_klines(filter, &app_state.db_pool).await;
+
+    let elapsed = start_time.elapsed();
+
+    println!("Klines route completed in: {}.{} ms", elapsed.as_millis(), elapsed.subsec_micros());
+
+    match try_klines {
+        Ok(klines) => {
+            HttpResponse::Ok().json(KlinesResponse { klines })
+        },
+        Err(e) => {
+            println!("error : {} while fetching klines", e);
+            CustomApiError::internal_error()
+        }
+    }
+}
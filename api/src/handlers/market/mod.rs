@@ -0,0 +1,28 @@
+use actix_web::{get, web::Data, HttpResponse};
+use common::types::market::MarketConfig;
+use serde::Serialize;
+
+use crate::entrypoint::AppState;
+
+#[derive(Serialize)]
+pub struct MarketResponse {
+    pub market: String,
+    #[serde(flatten)]
+    pub config: MarketConfig,
+}
+
+#[derive(Serialize)]
+pub struct MarketsResponse {
+    markets: Vec<MarketResponse>
+}
+
+#[get("/markets")]
+pub async fn get_markets(app_state:Data<AppState>) -> HttpResponse {
+
+    let markets = app_state.market_registry.markets.iter().map(|config| MarketResponse {
+        market: config.get_market(),
+        config: config.clone(),
+    }).collect();
+
+    HttpResponse::Ok().json(MarketsResponse{markets})
+}
@@ -2,4 +2,9 @@ pub mod health;
 pub mod order;
 pub mod depth;
 pub mod user;
-pub mod trade;
\ No newline at end of file
+pub mod trade;
+pub mod market;
+pub mod fill;
+pub mod kline;
+pub mod ticker;
+pub mod api_key;
\ No newline at end of file
@@ -1,50 +1,48 @@
 use std::time::Instant;
-use actix_web::{delete, web::{Data, Json}, Responder};
+use actix_web::{delete, web::{Data, Json, ReqData}, Responder};
 use common::{message::{api::{CancelOrderPayload, MessageFromApi}, engine::OrderCancelledResponse}};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::{entrypoint::AppState, services::redis::{PubSubService, RedisService}, utils::{engine_res_wrapper::get_engine_http_response, observer::Observer}};
+use crate::{entrypoint::AppState, middleware::auth::AuthenticatedUser, utils::{api_key::ApiKeyScope, engine_res_wrapper::get_engine_http_response, observer::Observer}};
 
 #[derive(Deserialize, Serialize)]
 pub struct CancelOrder{
-    pub user_id: String,
     pub order_id: String,
     pub market: String
 }
 
 #[delete("/order")]
-pub async fn cancel_order(app_state:Data<AppState> ,json:Json<CancelOrder>) -> impl Responder{
+pub async fn cancel_order(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>, json:Json<CancelOrder>) -> impl Responder{
+
+    if let Some(response) = user.check_scope(ApiKeyScope::Trade) {
+        return response;
+    }
 
     let now = Instant::now();
     let route = String::from("Cancel Single Order");
     
     let observer = Observer::new(now, route);
 
-    let guard = &app_state.redis_pool;
-    let conn_1 = guard.get().unwrap();
-    let mut conn_2 = guard.get().unwrap();
-
-    let mut redis_service = RedisService::new(conn_1);
-
     let payload = json.0;
-    let channel_to_subscribe = payload.order_id.clone();
 
-    let pub_sub =  conn_2.as_pubsub();
-    let mut pub_sub_service = PubSubService::new(pub_sub, &channel_to_subscribe);
+    // every request gets its own channel, so cancels
+    // of the same order don't get each others responses
+    let id = Uuid::new_v4().to_string();
 
     let cancel_order_payload = CancelOrderPayload {
+        id,
         market: payload.market,
         order_id: payload.order_id,
-        user_id: payload.user_id,
+        user_id: user.into_inner().user_id,
     };
 
     let message_from_api = MessageFromApi::CancelOrder(cancel_order_payload);
 
     get_engine_http_response::<OrderCancelledResponse>(
-        message_from_api, 
-        &mut redis_service, 
-        &mut pub_sub_service,
+        message_from_api,
+        &app_state.engine,
         observer
-    )
+    ).await
 
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+use actix_web::{get, web::{Data, Query, ReqData}, HttpResponse};
+use common::{message::db_filler::OrderStatus, types::order::OrderSide};
+use serde::{Deserialize, Serialize};
+use store::{Order, OrderCursor, OrderFilter};
+
+use crate::{entrypoint::AppState, errors::CustomApiError, middleware::auth::AuthenticatedUser, utils::{api_key::ApiKeyScope, pagination::{get_page_limit, MAX_PAGE_LIMIT}}};
+
+#[derive(Deserialize, Debug)]
+pub struct OrderHistoryQuery {
+    pub market: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub side: Option<OrderSide>,
+    // milliseconds, both inclusive
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<i64>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct OrderHistoryResponse {
+    orders: Vec<Order>,
+    next_cursor: Option<String>,
+}
+
+#[get("/orders/history")]
+pub async fn get_order_history(app_state:Data<AppState>, user:ReqData<AuthenticatedUser>, query:Query<OrderHistoryQuery>) -> HttpResponse {
+
+    if let Some(response) = user.check_scope(ApiKeyScope::Read) {
+        return response;
+    }
+
+    let start_time = Instant::now();
+
+    let query = query.into_inner();
+
+    let limit = match get_page_limit(query.limit) {
+        Some(limit) => limit,
+        None => return CustomApiError::bad_request(&format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+    };
+
+    let cursor = match query.cursor {
+        Some(cursor) => match OrderCursor::decode(&cursor) {
+            Some(cursor) => Some(cursor),
+            None => return CustomApiError::bad_request("invalid cursor"),
+        },
+        None => None,
+    };
+
+    let filter = OrderFilter {
+        user_id: user.into_inner().user_id,
+        market: query.market,
+        status: query.status.map(|status| status.to_string()),
+        side: query.side.map(|side| side.to_string()),
+        start_time: query.start_time,
+        end_time: query.end_time,
+        cursor,
+        limit,
+    };
+
+    let try_orders = Order::get_orders(filter, &app_state.db_pool).await;
+
+    let elapsed = start_time.elapsed();
+
+    println!("Order History route completed in: {}.{} ms", elapsed.as_millis(), elapsed.subsec_micros());
+
+    match try_orders {
+        Ok((orders, next_cursor)) => {
+            HttpResponse::Ok().json(OrderHistoryResponse {
+                orders,
+                next_cursor: next_cursor.map(|cursor| cursor.encode()),
+            })
+        },
+        Err(e) => {
+            println!("error : {} while fetching order history", e);
+            CustomApiError::internal_error()
+        }
+    }
+}
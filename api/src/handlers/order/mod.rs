@@ -1,4 +1,5 @@
 pub mod create;
 pub mod cancel;
 pub mod cancel_all;
-pub mod open_orders;
\ No newline at end of file
+pub mod open_orders;
+pub mod history;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::time::Instant;
+
+use actix_web::{get, web::{Data, Path}, HttpResponse, ResponseError};
+use common::{message::api::{MessageFromApi, TickerPayload}, types::ticker::Ticker};
+use serde::Serialize;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+use crate::{entrypoint::AppState, errors::CustomApiError, utils::{engine_res_wrapper::{get_engine_http_response, MessageResult}, observer::Observer}};
+
+#[derive(Serialize)]
+pub struct TickersResponse {
+    tickers: Vec<Ticker>,
+}
+
+#[get("/ticker/{market}")]
+pub async fn get_ticker(app_state:Data<AppState>, path:Path<String>) -> HttpResponse {
+
+    let now = Instant::now();
+    let route = String::from("Ticker");
+
+    let observer = Observer::new(now, route);
+
+    let id = Uuid::new_v4().to_string();
+
+    let message_from_api = MessageFromApi::GetTicker(TickerPayload {
+        id: id.clone(),
+        market: path.into_inner(),
+    });
+
+    get_engine_http_response::<Ticker>(
+        message_from_api,
+        &app_state.engine,
+        observer
+    ).await
+}
+
+/// the ticker of every market is requested at once,
+/// so the orderbooks answer them in parallel
+#[get("/ticker")]
+pub async fn get_tickers(app_state:Data<AppState>) -> HttpResponse {
+
+    let start_time = Instant::now();
+
+    let mut requests = JoinSet::new();
+
+    for config in app_state.market_registry.markets.iter() {
+
+        let message_from_api = MessageFromApi::GetTicker(TickerPayload {
+            id: Uuid::new_v4().to_string(),
+            market: config.get_market(),
+        });
+
+        let engine = app_state.engine.clone();
+
+        requests.spawn(async move { engine.send_message(message_from_api).await });
+    }
+
+    let mut tickers = vec![];
+
+    while let Some(res) = requests.join_next().await {
+
+        let message = match res {
+            Ok(Ok(message)) => message,
+            Ok(Err(e)) => return e.error_response(),
+            Err(e) => {
+                println!("error : {} while requesting ticker", e);
+                return CustomApiError::internal_error();
+            }
+        };
+
+        match serde_json::from_str::<MessageResult<Ticker>>(&message) {
+            Ok(Ok(ticker)) => tickers.push(ticker),
+            Ok(Err(e)) => println!("error : {} while getting ticker", e.message),
+            Err(e) => println!("deserial error : {:?}", e),
+        }
+    }
+
+    tickers.sort_by(|a, b| a.market.cmp(&b.market));
+
+    let elapsed = start_time.elapsed();
+
+    println!("Tickers route completed in: {}.{} ms", elapsed.as_millis(), elapsed.subsec_micros());
+
+    HttpResponse::Ok().json(TickersResponse { tickers })
+}
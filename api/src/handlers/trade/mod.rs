@@ -1,23 +1,53 @@
 use std::time::Instant;
 
-use actix_web::{get, web::Data, HttpResponse,ResponseError};
-use serde::Serialize;
-use store::Trade;
+use actix_web::{get, web::{Data, Path, Query}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use store::{MarketTrade, MarketTradeFilter, Trade};
 
-use crate::{entrypoint::AppState, errors::ApiError};
+use crate::{entrypoint::AppState, errors::CustomApiError, utils::pagination::{get_page_limit, MAX_PAGE_LIMIT}};
+
+#[derive(Deserialize, Debug)]
+pub struct TradeHistoryQuery {
+    pub limit: Option<i64>,
+    // trades from this id, else the latest trades
+    pub from_id: Option<i64>,
+    // milliseconds, both inclusive
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
 
 #[derive(Serialize)]
 pub struct TradeResponse{
-    trades: Vec<Trade>
+    trades: Vec<MarketTrade>
 }
 
-#[get("/trades")]
-pub async fn get_trade_history(app_state:Data<AppState>) -> HttpResponse{
+#[get("/trades/{market}")]
+pub async fn get_trade_history(app_state:Data<AppState>, path:Path<String>, query:Query<TradeHistoryQuery>) -> HttpResponse{
 
     let start_time = Instant::now();
 
+    let market = path.into_inner();
+    let query = query.into_inner();
+
+    if app_state.market_registry.get(&market).is_none() {
+        return CustomApiError::bad_request("invalid market");
+    }
+
+    let limit = match get_page_limit(query.limit) {
+        Some(limit) => limit,
+        None => return CustomApiError::bad_request(&format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)),
+    };
+
+    let filter = MarketTradeFilter {
+        market,
+        from_id: query.from_id,
+        start_time: query.start_time,
+        end_time: query.end_time,
+        limit,
+    };
+
     let pool = &app_state.db_pool;
-    let try_trades = Trade::get_trades(pool).await;
+    let try_trades = Trade::get_market_trades(filter, pool).await;
 
     let elapsed = start_time.elapsed();
     
@@ -29,7 +59,7 @@ pub async fn get_trade_history(app_state:Data<AppState>) -> HttpResponse{
         },
         Err(e)=> {
             println!("error : {} while fetching trades", e);
-            ApiError::InternalServerError.error_response()
+            CustomApiError::internal_error()
         }
     }
-}
\ No newline at end of file
+}
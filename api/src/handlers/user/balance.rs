@@ -1,38 +1,44 @@
 use std::time::Instant;
 
-use actix_web::{get, web::{Data, Path}, Responder};
-use common::message::{api::UserMessageFromApi, engine::UserBalanceResponse};
+use actix_web::{get, web::{Data, Query, ReqData}, Responder};
+use common::message::{api::{UserBalancePayload, UserMessageFromApi}, engine::UserBalanceResponse};
+use serde::Deserialize;
+use uuid::Uuid;
 
-use crate::{entrypoint::AppState, services::redis::{PubSubService, RedisService}, utils::{engine_res_wrapper::get_user_engine_http_response, observer::Observer}};
+use crate::{entrypoint::AppState, middleware::auth::AuthenticatedUser, utils::{api_key::ApiKeyScope, engine_res_wrapper::get_user_engine_http_response, observer::Observer}};
 
-#[get("user/{user_id}/balance")]
-pub async fn get_user_balance(app_state: Data<AppState>, path: Path<String>) -> impl Responder{
+#[derive(Deserialize, Debug)]
+pub struct BalanceQuery {
+    pub asset: Option<String>,
+}
 
-    let now = Instant::now();
-    let route = String::from("User Balance");
-    
-    let observer = Observer::new(now, route);
+#[get("/user/balance")]
+pub async fn get_user_balance(app_state: Data<AppState>, user: ReqData<AuthenticatedUser>, query: Query<BalanceQuery>) -> impl Responder{
 
-    let user_id = path.into_inner();
+    if let Some(response) = user.check_scope(ApiKeyScope::Read) {
+        return response;
+    }
 
-    let guard = &app_state.redis_pool;
-    let conn_1 = guard.get().unwrap();
-    let mut conn_2 = guard.get().unwrap();
+    let now = Instant::now();
+    let route = String::from("User Balance");
 
-    let mut redis_service = RedisService::new(conn_1);
-    let channel_to_subscribe = format!("{}-balance", user_id);
+    let observer = Observer::new(now, route);
 
-    let pub_sub =  conn_2.as_pubsub();
-    let mut pub_sub_service = PubSubService::new(pub_sub, &channel_to_subscribe);
+    let user_id = user.into_inner().user_id;
 
+    // every request gets its own channel, so concurrent
+    // requests of the user with different filters don't mix up
+    let channel_to_subscribe = Uuid::new_v4().to_string();
 
-    let user_message = UserMessageFromApi::Balance(user_id);
+    let user_message = UserMessageFromApi::Balance(UserBalancePayload {
+        id: channel_to_subscribe.clone(),
+        user_id,
+        asset: query.into_inner().asset,
+    });
 
     get_user_engine_http_response::<UserBalanceResponse>(
-        channel_to_subscribe.clone(), 
-        user_message, 
-        &mut redis_service, 
-        &mut pub_sub_service,
+        user_message,
+        &app_state.engine,
         observer
-    )
-}
\ No newline at end of file
+    ).await
+}
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use actix_web::{post, web::{Data, Json}, HttpResponse};
+use common::message::{api::{CreateUserPayload, UserMessageFromApi}, engine::UserCreatedResponse};
+use serde::{Deserialize, Serialize};
+use store::ApiKey;
+use uuid::Uuid;
+
+use crate::{entrypoint::AppState, errors::CustomApiError, utils::{api_key::{generate_api_key, ApiKeyScope}, engine_res_wrapper::get_user_engine_result, observer::Observer}};
+
+#[derive(Deserialize, Debug)]
+pub struct CreateUser {
+    // a new id is generated when not given
+    pub user_id: Option<String>,
+}
+
+/// the secret is only shown once, it can't be fetched again
+#[derive(Serialize)]
+pub struct CreateUserResponse {
+    user_id: String,
+    api_key: String,
+    secret: String,
+}
+
+#[post("/user")]
+pub async fn create_user(app_state: Data<AppState>, payload: Json<CreateUser>) -> HttpResponse{
+
+    let now = Instant::now();
+    let route = String::from("Create User");
+
+    let observer = Observer::new(now, route);
+
+    let id = Uuid::new_v4().to_string();
+
+    let user_id = match payload.0.user_id {
+        Some(user_id) if !user_id.is_empty() => user_id,
+        _ => Uuid::new_v4().to_string(),
+    };
+
+    // the first key of the user, more keys can be created with it.
+    // it is added before the user is created in the engine, so a user is never left without a key
+    let (key, api_key) = generate_api_key(&user_id, String::from("default"), &ApiKeyScope::DEFAULT, None);
+
+    if let Err(e) = ApiKey::add_api_key(&api_key, &app_state.db_pool).await {
+        println!("error : {} while adding api key of user : {}", e, user_id);
+        return CustomApiError::internal_error();
+    }
+
+    let user_message = UserMessageFromApi::CreateUser(CreateUserPayload {
+        id: id.clone(),
+        user_id,
+    });
+
+    let engine_res = get_user_engine_result::<UserCreatedResponse>(
+        user_message,
+        &app_state.engine,
+        observer
+    ).await;
+
+    let failed_response = match engine_res {
+        Ok(Ok(user_created)) => {
+            return HttpResponse::Ok().json(CreateUserResponse {
+                user_id: user_created.user_id,
+                api_key: key,
+                secret: api_key.secret,
+            });
+        },
+        Ok(Err(e)) => HttpResponse::BadRequest().json(e),
+        Err(response) => response,
+    };
+
+    // the user was not created, the key would belong to the user who already has the id or to no user
+    if let Err(e) = ApiKey::delete_api_key(&api_key.id, &api_key.user_id, &app_state.db_pool).await {
+        println!("error : {} while deleting api key of user : {} which was not created", e, api_key.user_id);
+    }
+
+    failed_response
+}
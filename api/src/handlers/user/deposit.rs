@@ -0,0 +1,38 @@
+use std::time::Instant;
+
+use actix_web::{post, web::{Data, Json, ReqData}, Responder};
+use common::message::{api::{BalanceChangePayload, UserMessageFromApi}, engine::BalanceUpdatedResponse};
+use uuid::Uuid;
+
+use crate::{entrypoint::AppState, handlers::user::BalanceChange, middleware::auth::AuthenticatedUser, utils::{api_key::ApiKeyScope, engine_res_wrapper::get_user_engine_http_response, observer::Observer}};
+
+#[post("/user/deposit")]
+pub async fn deposit(app_state: Data<AppState>, user: ReqData<AuthenticatedUser>, payload: Json<BalanceChange>) -> impl Responder{
+
+    if let Some(response) = user.check_scope(ApiKeyScope::Deposit) {
+        return response;
+    }
+
+    let now = Instant::now();
+    let route = String::from("User Deposit");
+
+    let observer = Observer::new(now, route);
+
+    let user_id = user.into_inner().user_id;
+
+    let id = Uuid::new_v4().to_string();
+    let payload = payload.0;
+
+    let user_message = UserMessageFromApi::Deposit(BalanceChangePayload {
+        id: id.clone(),
+        user_id,
+        asset: payload.asset,
+        amount: payload.amount,
+    });
+
+    get_user_engine_http_response::<BalanceUpdatedResponse>(
+        user_message,
+        &app_state.engine,
+        observer
+    ).await
+}
@@ -1 +1,14 @@
-pub mod balance;
\ No newline at end of file
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+pub mod balance;
+pub mod create;
+pub mod deposit;
+pub mod withdraw;
+
+/// Body of the deposit and withdraw requests
+#[derive(Deserialize, Debug)]
+pub struct BalanceChange {
+    pub asset: String,
+    pub amount: Decimal,
+}
@@ -5,6 +5,7 @@ pub mod handlers;
 pub mod errors;
 pub mod entrypoint;
 pub mod services;
+pub mod middleware;
 mod utils;
 
 #[actix_web::main]
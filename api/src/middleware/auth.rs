@@ -0,0 +1,128 @@
+use actix_web::{body::MessageBody, dev::{Payload, ServiceRequest, ServiceResponse}, middleware::Next, web::{Bytes, Data}, Error, HttpMessage, HttpResponse, ResponseError};
+use chrono::Utc;
+use common::auth::{hash_api_key, is_in_recv_window, is_ip_allowed, verify_signature, ApiKeyScope, DEFAULT_RECV_WINDOW, MAX_CLOCK_DRIFT, MAX_RECV_WINDOW};
+use store::ApiKey;
+
+use crate::{entrypoint::AppState, errors::ApiError, services::redis::save_signature, utils::api_key::get_scopes};
+
+pub const API_KEY_HEADER: &str = "X-API-KEY";
+pub const TIMESTAMP_HEADER: &str = "X-TIMESTAMP";
+pub const RECV_WINDOW_HEADER: &str = "X-RECV-WINDOW";
+pub const SIGNATURE_HEADER: &str = "X-SIGNATURE";
+
+/// User of the api key which signed the request, set by `authenticate` for the handlers
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: String,
+    // id of the api key, not the key itself
+    pub key_id: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub allowed_ips: Option<Vec<String>>,
+}
+
+impl AuthenticatedUser {
+
+    /// error response for the handler to return when the key doesn't have the scope
+    pub fn check_scope(&self, scope: ApiKeyScope) -> Option<HttpResponse> {
+
+        if self.scopes.contains(&scope) {
+            return None;
+        }
+
+        println!("api key : {} of user : {} doesn't have the {} scope", self.key_id, self.user_id, scope.as_str());
+
+        Some(ApiError::Forbidden("api key doesn't have the scope for this request").error_response())
+    }
+}
+
+fn get_header<'a>(req: &'a ServiceRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Verifies the signature of the request and resolves its api key to the user and its scopes.
+/// A request is accepted only within the recv window after its timestamp and only once,
+/// the signatures are remembered till the window is over.
+pub async fn authenticate(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+
+    let (api_key, timestamp, signature) = match (
+        get_header(&req, API_KEY_HEADER),
+        get_header(&req, TIMESTAMP_HEADER).and_then(|timestamp| timestamp.parse::<i64>().ok()),
+        get_header(&req, SIGNATURE_HEADER).and_then(|signature| hex::decode(signature).ok()),
+    ) {
+        (Some(api_key), Some(timestamp), Some(signature)) => (api_key.to_string(), timestamp, signature),
+        _ => return Err(ApiError::UnAuthorized("api key, timestamp and signature headers are required").into()),
+    };
+
+    let recv_window = match get_header(&req, RECV_WINDOW_HEADER) {
+        Some(recv_window) => match recv_window.parse::<i64>() {
+            Ok(recv_window) if recv_window > 0 && recv_window <= MAX_RECV_WINDOW => recv_window,
+            _ => return Err(ApiError::UnAuthorized("recv window must be between 1 and 60000 ms").into()),
+        },
+        None => DEFAULT_RECV_WINDOW,
+    };
+
+    let now = Utc::now().timestamp_millis();
+
+    if !is_in_recv_window(timestamp, recv_window, now) {
+        println!("request of api key : {} with timestamp : {} is outside the recv window at : {}", api_key, timestamp, now);
+        return Err(ApiError::UnAuthorized("timestamp is outside the recv window").into());
+    }
+
+    let app_state = match req.app_data::<Data<AppState>>() {
+        Some(app_state) => app_state.clone(),
+        None => return Err(ApiError::InternalServerError.into()),
+    };
+
+    let key = match ApiKey::get_api_key_by_hash(&hash_api_key(&api_key), &app_state.db_pool).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return Err(ApiError::UnAuthorized("invalid api key").into()),
+        Err(e) => {
+            println!("error : {} while fetching api key", e);
+            return Err(ApiError::InternalServerError.into());
+        }
+    };
+
+    // the peer of the connection, headers like X-Forwarded-For can be set by anyone
+    let ip = req.peer_addr().map(|addr| addr.ip());
+
+    if !is_ip_allowed(&key.allowed_ips, ip) {
+        println!("request of api key : {} from ip : {:?} which is not allowed", key.id, ip);
+        return Err(ApiError::Forbidden("ip address is not allowed for this api key").into());
+    }
+
+    // the body is read to verify the signature, then put back for the handler
+    let body = req.extract::<Bytes>().await?;
+    req.set_payload(Payload::from(body.clone()));
+
+    let path_and_query = req.uri().path_and_query().map_or("", |path| path.as_str());
+
+    // the signature is the HMAC-SHA256 of `timestamp + method + path and query + body` with the secret of the key
+    let mut payload = format!("{}{}{}", timestamp, req.method().as_str(), path_and_query).into_bytes();
+    payload.extend_from_slice(&body);
+
+    if !verify_signature(&key.secret, &payload, &signature) {
+        println!("invalid signature for api key : {}", api_key);
+        return Err(ApiError::UnAuthorized("invalid signature").into());
+    }
+
+    let mut conn = app_state.engine.clone_conn();
+
+    if !save_signature(&mut conn, &hex::encode(&signature), recv_window + MAX_CLOCK_DRIFT).await? {
+        println!("replayed request of api key : {}", api_key);
+        return Err(ApiError::UnAuthorized("request was already received").into());
+    }
+
+    let scopes = get_scopes(&key);
+
+    req.extensions_mut().insert(AuthenticatedUser {
+        user_id: key.user_id,
+        key_id: key.id,
+        scopes,
+        allowed_ips: key.allowed_ips,
+    });
+
+    next.call(req).await
+}
@@ -0,0 +1,152 @@
+use actix_web::{body::MessageBody, dev::{ServiceRequest, ServiceResponse}, http::Method, middleware::Next, web::Data, Error, HttpMessage};
+
+use crate::{entrypoint::AppState, errors::ApiError, middleware::auth::AuthenticatedUser, services::redis::take_tokens};
+
+/// Token bucket of a client, it holds up to `capacity` tokens and
+/// gets `refill_per_sec` tokens back every second
+#[derive(Debug, Clone, Copy)]
+pub struct BucketLimit {
+    pub capacity: u32,
+    pub refill_per_sec: u32,
+}
+
+impl BucketLimit {
+
+    fn load(name: &str, capacity: u32, refill_per_sec: u32) -> Self {
+        Self {
+            capacity: get_env_number(&format!("RATE_LIMIT_{}_CAPACITY", name)).unwrap_or(capacity),
+            refill_per_sec: get_env_number(&format!("RATE_LIMIT_{}_REFILL_PER_SEC", name)).unwrap_or(refill_per_sec),
+        }
+    }
+}
+
+/// Limits of the requests per ip, user and api key, a request takes
+/// its weight in tokens from every bucket it is counted in
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub ip: BucketLimit,
+    pub user: BucketLimit,
+    pub api_key: BucketLimit,
+    // requests placing or cancelling orders, and every other request which isn't a GET
+    pub order_weight: u32,
+    pub read_weight: u32,
+}
+
+fn get_env_number(name: &str) -> Option<u32> {
+    std::env::var(name).ok().and_then(|value| value.parse::<u32>().ok())
+}
+
+impl RateLimitConfig {
+
+    pub fn load() -> Self {
+
+        let config = Self {
+            ip: BucketLimit::load("IP", 120, 20),
+            user: BucketLimit::load("USER", 60, 10),
+            api_key: BucketLimit::load("API_KEY", 60, 10),
+            order_weight: get_env_number("RATE_LIMIT_ORDER_WEIGHT").unwrap_or(5),
+            read_weight: get_env_number("RATE_LIMIT_READ_WEIGHT").unwrap_or(1),
+        };
+
+        if let Err(e) = config.validate() {
+            panic!("Invalid rate limit config , error : {}", e);
+        }
+
+        config
+    }
+
+    fn validate(&self) -> Result<(), String> {
+
+        if self.read_weight == 0 || self.order_weight == 0 {
+            return Err(String::from("weights must be more than 0"));
+        }
+
+        for (name, limit) in [("ip", self.ip), ("user", self.user), ("api key", self.api_key)] {
+
+            if limit.refill_per_sec == 0 {
+                return Err(format!("refill of the {} bucket must be more than 0", name));
+            }
+
+            // a request heavier than the bucket would never go through
+            if limit.capacity < self.order_weight.max(self.read_weight) {
+                return Err(format!("capacity of the {} bucket must be at least the weight of a request", name));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_weight(&self, req: &ServiceRequest) -> u32 {
+        match *req.method() {
+            Method::GET => self.read_weight,
+            _ => self.order_weight,
+        }
+    }
+}
+
+/// takes the weight of the request from the buckets, the buckets are kept in redis
+/// so every api instance counts against the same limits
+async fn check_buckets(req: &ServiceRequest, buckets: &[(String, BucketLimit)]) -> Result<(), Error> {
+
+    let app_state = match req.app_data::<Data<AppState>>() {
+        Some(app_state) => app_state,
+        None => return Err(ApiError::InternalServerError.into()),
+    };
+
+    let weight = app_state.rate_limit.get_weight(req);
+
+    let mut conn = app_state.engine.clone_conn();
+
+    match take_tokens(&mut conn, buckets, weight).await? {
+        Some(retry_after_ms) => {
+            println!("rate limited request to : {} for buckets : {:?}", req.path(), buckets.iter().map(|(key, _)| key).collect::<Vec<_>>());
+            Err(ApiError::TooManyRequests(retry_after_ms).into())
+        },
+        None => Ok(()),
+    }
+}
+
+/// Limits the requests of every ip, before anything else is done for them
+pub async fn limit_ip(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+
+    // the peer of the connection, same as the ip allow list of the api keys
+    if let Some(addr) = req.peer_addr() {
+
+        let limit = match req.app_data::<Data<AppState>>() {
+            Some(app_state) => app_state.rate_limit.ip,
+            None => return Err(ApiError::InternalServerError.into()),
+        };
+
+        check_buckets(&req, &[(format!("rate_limit:ip:{}", addr.ip().to_canonical()), limit)]).await?;
+    }
+
+    next.call(req).await
+}
+
+/// Limits the requests of every user and of every api key of the user,
+/// it runs after `authenticate`, which sets the user of the request
+pub async fn limit_user(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+
+    let user = match req.extensions().get::<AuthenticatedUser>() {
+        Some(user) => user.clone(),
+        None => return Err(ApiError::InternalServerError.into()),
+    };
+
+    let config = match req.app_data::<Data<AppState>>() {
+        Some(app_state) => app_state.rate_limit,
+        None => return Err(ApiError::InternalServerError.into()),
+    };
+
+    check_buckets(&req, &[
+        (format!("rate_limit:user:{}", user.user_id), config.user),
+        (format!("rate_limit:api_key:{}", user.key_id), config.api_key),
+    ]).await?;
+
+    next.call(req).await
+}
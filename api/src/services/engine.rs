@@ -0,0 +1,182 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+
+use common::{channel::{ORDER_CHANNEL, USER_CHANNEL}, message::api::{MessageFromApi, UserMessageFromApi}};
+use redis::{aio::MultiplexedConnection, AsyncCommands, Msg, PushInfo, RedisError};
+use tokio::sync::{mpsc::UnboundedReceiver, oneshot};
+
+use crate::errors::ApiError;
+
+type PendingResponses = Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>;
+
+/// Sends the messages to the engine and routes its responses back to the requests waiting for them.
+/// The engine publishes every response on the channel of the request id, the channels are
+/// subscribed on one multiplexed connection shared by all the requests of the api
+#[derive(Clone)]
+pub struct EngineRouter {
+    conn: MultiplexedConnection,
+    pending: PendingResponses,
+    timeout: Duration,
+}
+
+/// Request waiting for its response, it is removed from the pending
+/// requests and its channel unsubscribed when it is done or dropped
+struct PendingRequest {
+    id: String,
+    conn: MultiplexedConnection,
+    pending: PendingResponses,
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+
+        if let Ok(mut guard) = self.pending.lock() {
+            guard.remove(&self.id);
+        }
+
+        let id = std::mem::take(&mut self.id);
+        let mut conn = self.conn.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = conn.unsubscribe(&id).await {
+                println!("Error : {} while unsubscribing from channel : {}", e, id);
+            }
+        });
+    }
+}
+
+impl EngineRouter {
+
+    pub async fn new() -> Self {
+        let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_e|String::from("redis://127.0.0.1:6379"));
+
+        // resp3 protocol needed for pubsubs
+        let address = format!("{}/?protocol=resp3", redis_url);
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let config = redis::AsyncConnectionConfig::new().set_push_sender(tx);
+        let client = redis::Client::open(address).expect("Failed to connect to Redis !");
+
+        let conn = client.get_multiplexed_async_connection_with_config(&config)
+        .await.expect("Failed to get Multiplexed conn");
+
+        let timeout_ms = std::env::var("ENGINE_TIMEOUT_MS")
+        .ok()
+        .and_then(|ms| ms.parse::<u64>().ok())
+        .unwrap_or(5000);
+
+        let pending: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+
+        tokio::spawn(EngineRouter::route_responses(rx, Arc::clone(&pending)));
+
+        Self {
+            conn,
+            pending,
+            timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+
+    async fn route_responses(mut rx: UnboundedReceiver<PushInfo>, pending: PendingResponses) {
+
+        while let Some(push_info) = rx.recv().await {
+
+            // the replies of subscribe and unsubscribe are pushed too, they aren't messages
+            let msg = match Msg::from_push_info(push_info) {
+                Some(msg) => msg,
+                None => continue,
+            };
+
+            let channel = msg.get_channel_name();
+
+            let message = match msg.get_payload::<String>() {
+                Ok(message) => message,
+                Err(e) => {
+                    println!("Error : {}, while receiving message on the channel : {}", e, channel);
+                    continue;
+                }
+            };
+
+            let sender = match pending.lock() {
+                Ok(mut guard) => guard.remove(channel),
+                Err(_) => None,
+            };
+
+            match sender {
+                Some(sender) => {
+                    // the request is gone if it timed out in the meantime
+                    let _ = sender.send(message);
+                },
+                None => println!("no request waiting for the message on the channel : {}", channel),
+            }
+        }
+
+        println!("connection to redis closed, the engine responses are not received anymore");
+    }
+
+    /// connection for the other commands of the api, the multiplexed connection is shared
+    pub fn clone_conn(&self) -> MultiplexedConnection {
+        self.conn.clone()
+    }
+
+    pub async fn send_message(&self, message: MessageFromApi) -> Result<String, ApiError> {
+
+        let serialized = serde_json::to_string(&message).map_err(|_|{
+            println!("Error while serializing message : {:?}", message);
+            ApiError::InternalServerError
+        })?;
+
+        self.request(message.get_channel_to_publish(), ORDER_CHANNEL, serialized).await
+    }
+
+    pub async fn send_user_message(&self, message: UserMessageFromApi) -> Result<String, ApiError> {
+
+        let serialized = serde_json::to_string(&message).map_err(|_|{
+            println!("Error while serializing message : {:?}", message);
+            ApiError::InternalServerError
+        })?;
+
+        self.request(message.get_channel_to_publish(), USER_CHANNEL, serialized).await
+    }
+
+    /// pushes the message to the queue of the engine and waits for the response on the channel of the id,
+    /// a gateway timeout if the engine doesn't respond in time
+    async fn request(&self, id: String, queue: &str, message: String) -> Result<String, ApiError> {
+
+        let (tx, rx) = oneshot::channel();
+
+        match self.pending.lock() {
+            Ok(mut guard) => guard.insert(id.clone(), tx),
+            Err(_) => return Err(ApiError::InternalServerError),
+        };
+
+        let mut conn = self.conn.clone();
+
+        let _request = PendingRequest {
+            id: id.clone(),
+            conn: conn.clone(),
+            pending: Arc::clone(&self.pending),
+        };
+
+        // subscribed before the message is pushed, so the response can't be missed
+        if let Err(e) = conn.subscribe(&id).await {
+            println!("Error : {} while subscribing to channel : {}", e, id);
+            return Err(ApiError::InternalServerError);
+        }
+
+        let pushed: Result<i64, RedisError> = conn.lpush(queue, message).await;
+
+        if let Err(e) = pushed {
+            println!("Error : {} while pushing message to the queue : {}", e, queue);
+            return Err(ApiError::InternalServerError);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(ApiError::InternalServerError),
+            Err(_) => {
+                println!("no response from the engine for request : {} in {} ms", id, self.timeout.as_millis());
+                Err(ApiError::GatewayTimeout)
+            }
+        }
+    }
+}
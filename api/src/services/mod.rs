@@ -1 +1,2 @@
+pub mod engine;
 pub mod redis;
\ No newline at end of file
@@ -1,102 +1,92 @@
-use common::{channel::{ORDER_CHANNEL, USER_CHANNEL}, message::api::{MessageFromApi, UserMessageFromApi}};
-use r2d2_redis::{r2d2::PooledConnection, redis::{Commands, PubSub}, RedisConnectionManager};
+use redis::{aio::MultiplexedConnection, RedisError, Script};
 
-use crate::errors::{ApiError};
+use crate::{errors::ApiError, middleware::rate_limit::BucketLimit};
 
-pub type RedisResult = Result<String, r2d2_redis::redis::RedisError>;
 pub type RedisServiceResult<T> = Result<T, ApiError>;
 
-pub struct RedisService {
-    conn: PooledConnection<RedisConnectionManager>
-}
-
-impl RedisService {
-
-    pub fn new(conn: PooledConnection<RedisConnectionManager>) -> Self{
-        Self { conn }
-    }
-
-    pub fn publish_message_to_engine(&mut self, message:MessageFromApi) -> RedisServiceResult<()>{
-
-        let serialized = serde_json::to_string(&message).map_err(|_|{
-            println!("Error while serializing message : {:?}", message);
-            ApiError::InternalServerError
-        })?;
-
-        let _:RedisResult  = self.conn.lpush(ORDER_CHANNEL, serialized);
-
-        Ok(())
-    }
-
-    pub fn publish_user_message_to_engine(&mut self, message:UserMessageFromApi) -> RedisServiceResult<()> {
-        let serialized = serde_json::to_string(&message).map_err(|_|{
-            println!("Error while serializing message : {:?}", message);
-            ApiError::InternalServerError
-        })?;
-
-        let _:RedisResult  = self.conn.lpush(USER_CHANNEL, serialized);
-
-        Ok(())
+// Takes the weight from every bucket in KEYS, or from none of them when one doesn't have enough tokens.
+// ARGV is the weight, then the capacity and the refill per second of each bucket. The time of redis
+// is used so the api instances agree on it. Returns 0 when allowed, else the ms till it would be.
+const TAKE_TOKENS_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local weight = tonumber(ARGV[1])
+local tokens = {}
+local retry_after = 0
+
+for i, key in ipairs(KEYS) do
+    local capacity = tonumber(ARGV[i * 2])
+    local refill = tonumber(ARGV[i * 2 + 1])
+    local bucket = redis.call('HMGET', key, 'tokens', 'ts')
+    local available = tonumber(bucket[1]) or capacity
+    local ts = tonumber(bucket[2]) or now
+
+    available = math.min(capacity, available + math.max(0, now - ts) * refill / 1000)
+    tokens[i] = available
+
+    if available < weight then
+        retry_after = math.max(retry_after, math.ceil((weight - available) * 1000 / refill))
+    end
+end
+
+if retry_after > 0 then
+    return retry_after
+end
+
+for i, key in ipairs(KEYS) do
+    local capacity = tonumber(ARGV[i * 2])
+    local refill = tonumber(ARGV[i * 2 + 1])
+    redis.call('HSET', key, 'tokens', tostring(tokens[i] - weight), 'ts', now)
+    -- a full bucket is the same as no bucket
+    redis.call('PEXPIRE', key, math.ceil(capacity * 1000 / refill))
+end
+
+return 0
+"#;
+
+/// saves the signature of a request for the given milliseconds,
+/// false if it was already saved, so the request is a replay
+pub async fn save_signature(conn:&mut MultiplexedConnection, signature:&str, expiry_ms:i64) -> RedisServiceResult<bool> {
+
+    let res: Result<Option<String>, RedisError> = redis::cmd("SET")
+    .arg(format!("signature:{}", signature))
+    .arg(1)
+    .arg("NX")
+    .arg("PX")
+    .arg(expiry_ms)
+    .query_async(conn)
+    .await;
+
+    match res {
+        Ok(saved) => Ok(saved.is_some()),
+        Err(e) => {
+            println!("Error : {} while saving signature", e);
+            Err(ApiError::InternalServerError)
+        }
     }
-
 }
 
-pub struct PubSubService<'a>{
-    pub_sub: PubSub<'a>,
-    channel: &'a str,
-}
+/// takes the weight from the token buckets, the ms to wait
+/// before retrying if one of them doesn't have enough tokens
+pub async fn take_tokens(conn:&mut MultiplexedConnection, buckets:&[(String, BucketLimit)], weight:u32) -> RedisServiceResult<Option<i64>> {
 
-impl<'a> PubSubService<'a> {
+    let script = Script::new(TAKE_TOKENS_SCRIPT);
+    let mut invocation = script.prepare_invoke();
 
-    pub fn new(pub_sub: PubSub<'a>, channel:&'a str) -> Self{
-        Self { pub_sub, channel }
-    }
+    invocation.arg(weight);
 
-    pub fn subscribe(&mut self) -> RedisServiceResult<()>{
-        self.pub_sub.subscribe(self.channel).map_err(|_|{
-            println!("Error while subscribing to channel : {:?}", self.channel);
-            ApiError::InternalServerError
-        })?;
-        Ok(())
+    for (key, limit) in buckets {
+        invocation.key(key).arg(limit.capacity).arg(limit.refill_per_sec);
     }
 
-    pub fn subscribe_to_channel(&mut self, channel:&str) -> RedisServiceResult<()> {
-
-        self.pub_sub.subscribe(channel).map_err(|_|{
-            println!("Error while subscribing to channel : {:?}", channel);
-            ApiError::InternalServerError
-        })?;
-
-        Ok(())
-    }
+    let res: Result<i64, RedisError> = invocation.invoke_async(conn).await;
 
-    pub fn get_message_from_engine(&mut self) -> RedisServiceResult<String>{
-
-        let message = self.pub_sub.get_message().map_err(|e|{
-            println!("Error while receiving message from engine : {:?}", e);
-            ApiError::InternalServerError
-        })?;
-
-        let payload: RedisResult = message.get_payload();
-        
-        match payload {
-            Ok(val) => {
-                Ok(val)
-            },
-            Err(e) => {
-                println!("Error while getting payload from message : {}", e);
-                Err(ApiError::InternalServerError)
-            }
+    match res {
+        Ok(0) => Ok(None),
+        Ok(retry_after_ms) => Ok(Some(retry_after_ms)),
+        Err(e) => {
+            println!("Error : {} while taking tokens from the rate limit buckets", e);
+            Err(ApiError::InternalServerError)
         }
     }
-
-    pub fn unsubscribe(&mut self) -> RedisServiceResult<()>{
-
-        let res = self.pub_sub.unsubscribe(self.channel).map_err(|e|{
-            println!("Error : {} while unsubscribing from channel : {}", e, self.channel);
-            ApiError::InternalServerError
-        });
-
-        res
-    }
-}
\ No newline at end of file
+}
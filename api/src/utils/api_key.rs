@@ -0,0 +1,99 @@
+use std::net::IpAddr;
+
+use chrono::Utc;
+pub use common::auth::ApiKeyScope;
+use common::auth::hash_api_key;
+use store::ApiKey;
+use uuid::Uuid;
+
+const KEY_PREFIX_LEN: usize = 8;
+
+/// scopes of a stored key, unknown scopes are dropped
+pub fn get_scopes(api_key: &ApiKey) -> Vec<ApiKeyScope> {
+    api_key.scopes.iter().filter_map(|scope| ApiKeyScope::parse(scope)).collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AllowedIpsError {
+    Invalid(String),
+    // the key creating it has an allow list which these ips aren't in
+    NotAllowed,
+}
+
+/// canonical ips of a new key, when the key creating it has an allow list the new key
+/// needs one too and it can't be wider, else the new key could be used from anywhere
+pub fn get_allowed_ips(ips: Option<Vec<String>>, caller_ips: &Option<Vec<String>>) -> Result<Option<Vec<IpAddr>>, AllowedIpsError> {
+
+    let mut allowed_ips = vec![];
+
+    for ip in ips.unwrap_or_default() {
+        match ip.trim().parse::<IpAddr>() {
+            Ok(ip) => allowed_ips.push(ip.to_canonical()),
+            Err(_) => return Err(AllowedIpsError::Invalid(ip)),
+        }
+    }
+
+    allowed_ips.sort();
+    allowed_ips.dedup();
+
+    if let Some(caller_ips) = caller_ips.as_ref().filter(|caller_ips| !caller_ips.is_empty()) {
+
+        let caller_ips: Vec<IpAddr> = caller_ips.iter().filter_map(|ip| ip.parse::<IpAddr>().ok()).map(|ip| ip.to_canonical()).collect();
+
+        if allowed_ips.is_empty() || allowed_ips.iter().any(|ip| !caller_ips.contains(ip)) {
+            return Err(AllowedIpsError::NotAllowed);
+        }
+    }
+
+    if allowed_ips.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(allowed_ips))
+}
+
+/// new key of the user with the key to return, the secret is made of two random uuids so it has 244 random bits
+pub fn generate_api_key(user_id: &str, label: String, scopes: &[ApiKeyScope], allowed_ips: Option<Vec<IpAddr>>) -> (String, ApiKey) {
+
+    let key = Uuid::new_v4().simple().to_string();
+
+    let api_key = ApiKey {
+        id: Uuid::new_v4().to_string(),
+        key_hash: hash_api_key(&key),
+        key_prefix: key[..KEY_PREFIX_LEN].to_string(),
+        secret: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        user_id: user_id.to_string(),
+        label,
+        scopes: scopes.iter().map(|scope| scope.as_str().to_string()).collect(),
+        allowed_ips: allowed_ips.map(|ips| ips.iter().map(|ip| ip.to_string()).collect()),
+        created_at: Utc::now().timestamp_millis(),
+    };
+
+    (key, api_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ips(ips: &[&str]) -> Option<Vec<String>> {
+        Some(ips.iter().map(|ip| ip.to_string()).collect())
+    }
+
+    #[test]
+    fn key_without_allow_list_can_create_any_key() {
+        assert_eq!(get_allowed_ips(None, &None), Ok(None));
+        assert_eq!(get_allowed_ips(ips(&["10.0.0.2", " 10.0.0.1", "10.0.0.2"]), &None), Ok(Some(vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()])));
+        assert_eq!(get_allowed_ips(ips(&["not an ip"]), &None), Err(AllowedIpsError::Invalid(String::from("not an ip"))));
+    }
+
+    #[test]
+    fn restricted_key_can_only_create_keys_within_its_allow_list() {
+        let caller_ips = ips(&["10.0.0.1", "10.0.0.2"]);
+
+        assert_eq!(get_allowed_ips(None, &caller_ips), Err(AllowedIpsError::NotAllowed));
+        assert_eq!(get_allowed_ips(ips(&[]), &caller_ips), Err(AllowedIpsError::NotAllowed));
+        assert_eq!(get_allowed_ips(ips(&["10.0.0.1", "10.0.0.3"]), &caller_ips), Err(AllowedIpsError::NotAllowed));
+        assert_eq!(get_allowed_ips(ips(&["::ffff:10.0.0.2"]), &caller_ips), Ok(Some(vec!["10.0.0.2".parse().unwrap()])));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod api_key;
 pub mod engine_res_wrapper;
-pub mod observer;
\ No newline at end of file
+pub mod observer;
+pub mod pagination;
\ No newline at end of file
@@ -0,0 +1,13 @@
+/// page size when the limit is not given
+pub const DEFAULT_PAGE_LIMIT: i64 = 100;
+pub const MAX_PAGE_LIMIT: i64 = 1000;
+
+/// limit of the page, None if it is out of the allowed range
+pub fn get_page_limit(limit: Option<i64>) -> Option<i64> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+
+    match limit > 0 && limit <= MAX_PAGE_LIMIT {
+        true => Some(limit),
+        false => None,
+    }
+}
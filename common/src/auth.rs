@@ -0,0 +1,82 @@
+use std::net::IpAddr;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// milliseconds a signed request is valid for after its timestamp
+pub const DEFAULT_RECV_WINDOW: i64 = 5000;
+pub const MAX_RECV_WINDOW: i64 = 60000;
+// milliseconds the clock of the client can be ahead of the server
+pub const MAX_CLOCK_DRIFT: i64 = 1000;
+
+/// What a request signed with the api key is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    // orders, fills, balances and api keys of the user, and the private ws channels
+    Read,
+    // placing and cancelling orders
+    Trade,
+    Withdraw,
+    // deposits credit the balance without any transfer, so the users can't give it to their keys
+    Deposit,
+}
+
+impl ApiKeyScope {
+
+    pub const ALL: [ApiKeyScope; 4] = [ApiKeyScope::Read, ApiKeyScope::Trade, ApiKeyScope::Withdraw, ApiKeyScope::Deposit];
+
+    // scopes of the first key of a new user
+    pub const DEFAULT: [ApiKeyScope; 3] = [ApiKeyScope::Read, ApiKeyScope::Trade, ApiKeyScope::Withdraw];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::Read => "read",
+            ApiKeyScope::Trade => "trade",
+            ApiKeyScope::Withdraw => "withdraw",
+            ApiKeyScope::Deposit => "deposit",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<ApiKeyScope> {
+        ApiKeyScope::ALL.into_iter().find(|s| s.as_str() == scope)
+    }
+}
+
+/// hex encoded sha256 of the key, which is what the key is stored and looked up by
+pub fn hash_api_key(api_key: &str) -> String {
+    hex::encode(Sha256::digest(api_key.as_bytes()))
+}
+
+/// true if the signature is the HMAC-SHA256 of the payload with the secret
+pub fn verify_signature(secret: &str, payload: &[u8], signature: &[u8]) -> bool {
+
+    let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+
+    mac.update(payload);
+
+    mac.verify_slice(signature).is_ok()
+}
+
+/// a request is accepted from the timestamp, allowing for the drift of the clock, till the window is over
+pub fn is_in_recv_window(timestamp: i64, recv_window: i64, now: i64) -> bool {
+    timestamp <= now + MAX_CLOCK_DRIFT && now - timestamp <= recv_window
+}
+
+/// the key can be used from any ip when it has no allow list
+pub fn is_ip_allowed(allowed_ips: &Option<Vec<String>>, ip: Option<IpAddr>) -> bool {
+
+    let allowed_ips = match allowed_ips {
+        Some(allowed_ips) if !allowed_ips.is_empty() => allowed_ips,
+        _ => return true,
+    };
+
+    match ip {
+        Some(ip) => allowed_ips.iter().any(|allowed_ip| allowed_ip.parse::<IpAddr>().is_ok_and(|allowed_ip| allowed_ip == ip.to_canonical())),
+        None => false,
+    }
+}
@@ -1,9 +1,16 @@
 
 // orders from api to engine are pushed in this channel
-pub const ORDER_CHANNEL: &'static str = "orders";
+pub const ORDER_CHANNEL: &str = "orders";
 
 // db updated from db_filler are pushed in this channel
-pub const DB_CHANNEL:&'static str = "db_filler";
+pub const DB_CHANNEL:&str = "db_filler";
 
 // user queries are pushed in this channel
-pub const USER_CHANNEL: &'static str = "user";
\ No newline at end of file
+pub const USER_CHANNEL: &str = "user";
+
+// public ws channels are named <stream>@<market>, the klines kline_<interval>@<market>
+pub const PUBLIC_WS_STREAMS: [&str; 3] = ["trade", "depth", "ticker"];
+pub const KLINE_WS_STREAM_PREFIX: &str = "kline_";
+
+// private ws channels are named <stream>@<user_id>, only the logged in user can subscribe to them
+pub const PRIVATE_WS_STREAMS: [&str; 3] = ["order", "fill", "balance"];
\ No newline at end of file
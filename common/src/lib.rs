@@ -1,3 +1,4 @@
 pub mod types;
 pub mod message;
-pub mod channel;
\ No newline at end of file
+pub mod channel;
+pub mod auth;
\ No newline at end of file
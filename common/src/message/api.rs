@@ -1,6 +1,7 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{order::{OrderSide, OrderType, Price, Quantity}};
+use crate::types::{order::{OrderSide, OrderType, Price, Quantity, TimeInForce}};
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub enum MessageFromApi{
@@ -8,7 +9,8 @@ pub enum MessageFromApi{
     CancelOrder(CancelOrderPayload),
     CancelAllOrders(CancelOrdersPayload),
     GetAllOpenOrders(OpenOrdersPayload),
-    GetDepth(String),
+    GetDepth(DepthPayload),
+    GetTicker(TickerPayload),
 }
 
 impl MessageFromApi {
@@ -18,31 +20,77 @@ impl MessageFromApi {
             MessageFromApi::CancelOrder(order) => &order.market,
             MessageFromApi::CancelAllOrders(order) => &order.market,
             MessageFromApi::GetAllOpenOrders(order) => &order.market,
-            MessageFromApi::GetDepth(order) => order
+            MessageFromApi::GetDepth(payload) => &payload.market,
+            MessageFromApi::GetTicker(payload) => &payload.market,
         }
     }
 
+    /// messages which change the orderbooks or the balances, only they are journaled
+    pub fn is_state_change(&self) -> bool {
+        matches!(self, MessageFromApi::CreateOrder(_) | MessageFromApi::CancelOrder(_) | MessageFromApi::CancelAllOrders(_))
+    }
+
     pub fn get_channel_to_publish(&self)->String{
         match self{
             MessageFromApi::CreateOrder(order) => order.id.clone(),
-            MessageFromApi::CancelOrder(order) => order.order_id.clone(),
-            MessageFromApi::CancelAllOrders(order) => order.user_id.clone(), // send message on the users channel
-            MessageFromApi::GetAllOpenOrders(order) => order.user_id.clone(), // send message on the users channel
-            MessageFromApi::GetDepth(order) => order.clone()
+            MessageFromApi::CancelOrder(order) => order.id.clone(),
+            MessageFromApi::CancelAllOrders(order) => order.id.clone(),
+            MessageFromApi::GetAllOpenOrders(order) => order.id.clone(),
+            MessageFromApi::GetDepth(payload) => payload.id.clone(),
+            MessageFromApi::GetTicker(payload) => payload.id.clone(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum UserMessageFromApi {
-    // add messages like user create, user deposit
-    Balance(String)
+    Balance(UserBalancePayload),
+    CreateUser(CreateUserPayload),
+    Deposit(BalanceChangePayload),
+    Withdraw(BalanceChangePayload),
 }
 
 impl UserMessageFromApi {
     pub fn try_deserialized(serialized:&str) -> Result<Self, serde_json::Error> {
         serde_json::from_str::<Self>(serialized)
     }
+
+    /// messages which change the balances, they are journaled like the orders
+    pub fn is_balance_change(&self) -> bool {
+        !matches!(self, UserMessageFromApi::Balance(_))
+    }
+
+    pub fn get_channel_to_publish(&self) -> String {
+        match self {
+            UserMessageFromApi::Balance(payload) => payload.id.clone(),
+            UserMessageFromApi::CreateUser(payload) => payload.id.clone(),
+            UserMessageFromApi::Deposit(payload) => payload.id.clone(),
+            UserMessageFromApi::Withdraw(payload) => payload.id.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct UserBalancePayload {
+    pub id: String,
+    pub user_id: String,
+    // only the balance of this asset is returned when set
+    pub asset: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct CreateUserPayload {
+    pub id: String,
+    pub user_id: String,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct BalanceChangePayload {
+    pub id: String,
+    pub user_id: String,
+    pub asset: String,
+    // in the units of the asset, not lamports
+    pub amount: Decimal,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -52,12 +100,22 @@ pub struct CreateOrderPayload {
     pub side: OrderSide,
     pub market: String,
     pub order_type: OrderType,
-    pub price: Price,
-    pub quantity: Quantity,
+    // not needed for market orders, they fill at the best available prices
+    pub price: Option<Price>,
+    pub quantity: Option<Quantity>,
+    // market orders can be sized by the quote amount instead of the quantity
+    #[serde(default)]
+    pub quote_quantity: Option<Quantity>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    // needed only for the stop and take profit orders
+    #[serde(default)]
+    pub trigger_price: Option<Price>,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct CancelOrderPayload {
+    pub id: String,
     pub market: String,
     pub order_id: String,
     pub user_id: String,
@@ -65,12 +123,30 @@ pub struct CancelOrderPayload {
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct CancelOrdersPayload {
+    pub id: String,
     pub market: String,
     pub user_id: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct OpenOrdersPayload{
+    pub id: String,
     pub market: String,
     pub user_id: String,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct DepthPayload {
+    pub id: String,
+    pub market: String,
+    // number of price levels on each side, all the levels if None
+    pub limit: Option<usize>,
+    // levels are merged into buckets of this size, must be a multiple of the tick size
+    pub grouping: Option<Price>,
+}
+
+#[derive(Deserialize, Debug, Clone, Serialize)]
+pub struct TickerPayload {
+    pub id: String,
+    pub market: String,
+}
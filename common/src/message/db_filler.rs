@@ -2,7 +2,9 @@ use std::{fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::order::{OrderSide, Price, Quantity};
+use rust_decimal::Decimal;
+
+use crate::types::order::{OrderSide, OrderType, Price, Quantity};
 
 /// Message from engine to db filler
 #[derive(Serialize, Deserialize)]
@@ -12,22 +14,25 @@ pub enum DbFillerMessage{
         add_order: Option<AddOrderToDb>,
         update_orders: Vec<UpdateOrder>,
     },
-    UpdateCancelOrders(Vec<String>)
+    UpdateCancelOrders{
+        // only the open orders of this user are cancelled
+        user_id: String,
+        order_ids: Vec<String>,
+        timestamp: i64,
+    },
+    AddLedgerEntry(LedgerEntry),
 }
 
 impl DbFillerMessage {
     pub fn get_deserialized(message:&str) -> Option<DbFillerMessage>{
-        let res = serde_json::from_str(message)
-        .map_or(None, |val:DbFillerMessage|{
-            Some(val)
-        });
-
-        res
+        serde_json::from_str(message).ok()
     }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub enum OrderStatus{
+    // trigger order waiting for its trigger price
+    Pending,
     Open,
     Filled,
     Cancelled,
@@ -36,6 +41,7 @@ pub enum OrderStatus{
 impl Display for OrderStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::Pending => write!(f, "Pending"),
             Self::Open => write!(f, "Open"),
             Self::Filled => write!(f,"Filled"),
             Self::Cancelled => write!(f, "Cancelled")
@@ -45,22 +51,38 @@ impl Display for OrderStatus {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Trade {
-    pub id: u32,
+    // unique within the market
+    pub id: u64,
     pub market: String,
     pub price: Price,
     pub quantity: Quantity,
     pub quote_qty: Quantity,
     pub timestamp: i64,
+    pub maker_fee: Decimal,
+    pub maker_fee_asset: String,
+    pub taker_fee: Decimal,
+    pub taker_fee_asset: String,
+    pub maker_order_id: String,
+    pub taker_order_id: String,
+    pub maker_user_id: String,
+    pub taker_user_id: String,
+    // the buy order was resting on the book, so the taker sold
+    pub is_buyer_maker: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AddOrderToDb{
     pub order_id: String,
+    pub user_id: String,
+    pub market: String,
+    pub order_type: OrderType,
     pub quantity: Quantity, 
     pub filled_quantity: Quantity,
     pub price: Price,
     pub side: OrderSide,
     pub status: OrderStatus,
+    // time the order was accepted by the engine
+    pub timestamp: i64,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -68,4 +90,32 @@ pub struct UpdateOrder{
     pub order_id: String,
     pub filled_quantity:Quantity,
     pub status: OrderStatus, 
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum LedgerEntryType {
+    Deposit,
+    Withdraw,
+}
+
+impl Display for LedgerEntryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Deposit => write!(f, "Deposit"),
+            Self::Withdraw => write!(f, "Withdraw"),
+        }
+    }
+}
+
+/// Movement of funds in or out of the exchange,
+/// id is the journal sequence of the movement
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LedgerEntry {
+    pub id: u64,
+    pub user_id: String,
+    pub asset: String,
+    pub amount: Decimal,
+    pub entry_type: LedgerEntryType,
+    pub timestamp: i64,
 }
@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{order::{OrderSide, Price, Quantity}};
+use crate::{message::db_filler::LedgerEntryType, types::{order::{OrderSide, OrderType, Price, Quantity}, ticker::Ticker}};
 
 #[derive(Serialize, Deserialize)]
 pub enum MessageFromEngine{
@@ -9,7 +9,8 @@ pub enum MessageFromEngine{
     OrderCancelled(OrderCancelledResponse),
     AllOrdersCancelled(OrdersCancelledResponse),
     AllOpenOrders(AllOpenOrdersResponse),
-    GetDepth(DepthResponse)
+    GetDepth(DepthResponse),
+    Ticker(Ticker),
 }
 
 type EngineResult<T> = Result<T, ()>;
@@ -20,23 +21,27 @@ impl MessageFromEngine{
         match self{
             MessageFromEngine::OrderCancelled(data) => {
                 let ok_data: EngineResult<&OrderCancelledResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             } ,
             MessageFromEngine::OrderPlaced(data) => {
                 let ok_data: EngineResult<&OrderPlacedResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             },
             MessageFromEngine::AllOrdersCancelled(data) => {
                 let ok_data: EngineResult<&OrdersCancelledResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             },
             MessageFromEngine::AllOpenOrders(data) => {
                 let ok_data: EngineResult<&AllOpenOrdersResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             },
             MessageFromEngine::GetDepth(data) => {
                 let ok_data: EngineResult<&DepthResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
+            },
+            MessageFromEngine::Ticker(data) => {
+                let ok_data: EngineResult<&Ticker> = Ok(data);
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             },
         }   
     }
@@ -46,6 +51,8 @@ impl MessageFromEngine{
 #[derive(Serialize, Deserialize)]
 pub enum UserMessageFromEngine {
     Balance(UserBalanceResponse),
+    UserCreated(UserCreatedResponse),
+    BalanceUpdated(BalanceUpdatedResponse),
 }
 
 impl UserMessageFromEngine {
@@ -54,8 +61,16 @@ impl UserMessageFromEngine {
         match self{
             UserMessageFromEngine::Balance(data) => {
                 let ok_data: EngineResult<&UserBalanceResponse> = Ok(data);
-                serde_json::to_string(&ok_data).unwrap_or_else(|_|err_msg)
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
             } ,
+            UserMessageFromEngine::UserCreated(data) => {
+                let ok_data: EngineResult<&UserCreatedResponse> = Ok(data);
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
+            },
+            UserMessageFromEngine::BalanceUpdated(data) => {
+                let ok_data: EngineResult<&BalanceUpdatedResponse> = Ok(data);
+                serde_json::to_string(&ok_data).unwrap_or(err_msg)
+            },
         }   
     }
 }
@@ -66,16 +81,63 @@ pub struct UserBalanceResponse {
     pub balances: Vec<AssetAndBalance>
 }
 
-#[derive(Deserialize, Serialize)]
+/// Balance of an asset in the units of the asset,
+/// and in lamports as it is held by the engine
+#[derive(Debug, Deserialize, Serialize)]
 pub struct AssetAndBalance {
     pub asset: String,
-    pub balance: u64,
+    pub decimals: u8,
+    pub available: Decimal,
+    pub locked: Decimal,
+    pub total: Decimal,
+    pub available_lamports: u64,
+    pub locked_lamports: u64,
+    pub total_lamports: u64,
+}
+
+impl AssetAndBalance {
+
+    pub fn from_lamports(asset:&str, decimals:u8, available_lamports:u64, locked_lamports:u64) -> Self {
+
+        let lamports = Decimal::from(10_u64.pow(u32::from(decimals)));
+        let total_lamports = available_lamports + locked_lamports;
+
+        Self {
+            asset: asset.to_owned(),
+            decimals,
+            available: (Decimal::from(available_lamports) / lamports).normalize(),
+            locked: (Decimal::from(locked_lamports) / lamports).normalize(),
+            total: (Decimal::from(total_lamports) / lamports).normalize(),
+            available_lamports,
+            locked_lamports,
+            total_lamports,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UserCreatedResponse {
+    pub user_id: String,
+}
+
+/// Response for a deposit or a withdrawal
+#[derive(Deserialize, Serialize)]
+pub struct BalanceUpdatedResponse {
+    pub ledger_id: u64,
+    pub user_id: String,
+    pub asset: String,
+    pub amount: Decimal,
+    pub entry_type: LedgerEntryType,
+    // available balance of the asset after the update
+    pub available_amount: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OrderPlacedResponse {
     pub order_id: String,
     pub executed_quantity: Quantity,
+    // unfilled quantity which was cancelled instead of being added to the book
+    pub cancelled_quantity: Quantity,
     pub fills: Vec<OrderFill>
 }
 
@@ -85,7 +147,10 @@ pub struct OrderFill{
     pub price: Price,
     pub quantity: Quantity,
     pub filled_quantity: Quantity,
-    pub trade_id: u32,
+    pub trade_id: u64,
+    // fee paid by the taker on the fill
+    pub fee: Decimal,
+    pub fee_asset: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -94,6 +159,7 @@ pub struct OrderCancelledResponse {
     pub quantity: Quantity,
     pub executed_quantity: Quantity,
     pub side: OrderSide,
+    pub price: Price,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -114,6 +180,8 @@ pub struct OpenOrder{
     pub executed_quantity: Quantity,
     pub side: OrderSide,
     pub price: Price,
+    pub order_type: OrderType,
+    pub trigger_price: Option<Price>,
 }
 
 pub type AllOpenOrdersResponse = Vec<OpenOrder>;
@@ -123,5 +191,7 @@ pub type AllOpenOrdersResponse = Vec<OpenOrder>;
 /// second element is quantity 
 pub struct DepthResponse{
     pub bids: Vec<[Decimal;2]>,
-    pub asks: Vec<[Decimal;2]>
+    pub asks: Vec<[Decimal;2]>,
+    // id of the last depth update applied on the book
+    pub last_update_id: u64,
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::types::order::{Price, Quantity};
+use crate::{message::{db_filler::{OrderStatus, Trade}, engine::AssetAndBalance}, types::{kline::Kline, order::{OrderSide, Price, Quantity}, ticker::Ticker}};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum WsMessage{
@@ -9,29 +9,118 @@ pub enum WsMessage{
     Depth{
         depth: DepthUpdate
     },
+    Kline(Kline),
+    Ticker(Ticker),
+    Order(OrderUpdate),
+    Fill(FillUpdate),
+    Balance(BalanceUpdate),
+    // reply to the login of a client, its private channels are named with the user id
+    Login{
+        user_id: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TradeUpdate {
     pub e: String,
-    pub t: u32,
+    pub t: u64,
     pub p: Price,
     pub q: Quantity,
     pub s: String,
+    // buyer and seller order ids, users are not shared on the public channel
+    pub b: String,
+    pub a: String,
+    // is the buyer the maker
+    pub m: bool,
 }
 
+/// changed levels of the book with their new quantity, 0 when the level is removed.
+/// every changed level takes the next update id of the market,
+/// so the update covers the ids from first_update_id to last_update_id
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepthUpdate{
     pub bids: Vec<[Decimal;2]>,
-    pub asks: Vec<[Decimal;2]>
+    pub asks: Vec<[Decimal;2]>,
+    pub first_update_id: u64,
+    pub last_update_id: u64,
+}
+/// change to an order of the user, sent on its order@<user_id> channel
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderUpdate {
+    pub user_id: String,
+    pub order_id: String,
+    pub market: String,
+    pub side: OrderSide,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub filled_quantity: Quantity,
+    pub status: OrderStatus,
+    pub timestamp: i64,
 }
 
-impl DepthUpdate {
-    pub fn new() -> Self {
-        Self { bids: vec![], asks: vec![] }
-    }
+/// fill of an order of the user, sent on its fill@<user_id> channel.
+/// the fee is paid in the asset received, a negative fee is a rebate
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FillUpdate {
+    pub user_id: String,
+    pub trade_id: u64,
+    pub order_id: String,
+    pub market: String,
+    pub side: OrderSide,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub fee: Decimal,
+    pub fee_asset: String,
+    pub is_maker: bool,
+    pub timestamp: i64,
+}
+
+impl FillUpdate {
+
+    /// fills of the maker and the taker of the trade
+    pub fn from_trade(trade:&Trade) -> [FillUpdate; 2] {
 
-    pub fn from_value(bids: Vec<[Decimal; 2]>, asks:Vec<[Decimal; 2]>) -> Self{
-        Self { bids, asks }
+        let (maker_side, taker_side) = match trade.is_buyer_maker {
+            true => (OrderSide::Buy, OrderSide::Sell),
+            false => (OrderSide::Sell, OrderSide::Buy),
+        };
+
+        let maker_fill = FillUpdate {
+            user_id: trade.maker_user_id.clone(),
+            trade_id: trade.id,
+            order_id: trade.maker_order_id.clone(),
+            market: trade.market.clone(),
+            side: maker_side,
+            price: trade.price,
+            quantity: trade.quantity,
+            fee: trade.maker_fee,
+            fee_asset: trade.maker_fee_asset.clone(),
+            is_maker: true,
+            timestamp: trade.timestamp,
+        };
+
+        let taker_fill = FillUpdate {
+            user_id: trade.taker_user_id.clone(),
+            trade_id: trade.id,
+            order_id: trade.taker_order_id.clone(),
+            market: trade.market.clone(),
+            side: taker_side,
+            price: trade.price,
+            quantity: trade.quantity,
+            fee: trade.taker_fee,
+            fee_asset: trade.taker_fee_asset.clone(),
+            is_maker: false,
+            timestamp: trade.timestamp,
+        };
+
+        [maker_fill, taker_fill]
     }
-}
\ No newline at end of file
+}
+
+/// balances of the assets of the user which changed, sent on its balance@<user_id> channel
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    pub user_id: String,
+    pub balances: Vec<AssetAndBalance>,
+    pub timestamp: i64,
+}
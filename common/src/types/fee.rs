@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Fee rates charged on the amount received in a trade,
+/// a negative maker rate is a rebate paid to the maker
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub maker_rate: Decimal,
+    pub taker_rate: Decimal,
+}
+
+/// Fee tiers of the exchange and the users who don't pay the default tier,
+/// loaded from the json file at `FEES_CONFIG`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    // user id whose balances collect the fees and pay the rebates
+    pub fee_account: String,
+    pub default_tier: String,
+    pub tiers: HashMap<String, FeeTier>,
+    // user id to tier name
+    #[serde(default)]
+    pub user_tiers: HashMap<String, String>,
+}
+
+impl FeeSchedule {
+
+    pub fn get_path() -> String {
+        std::env::var("FEES_CONFIG").unwrap_or_else(|_|String::from("fees.json"))
+    }
+
+    /// panics if the config can't be read or is invalid,
+    /// as no trade can be settled without the fees
+    pub fn load() -> Self {
+        let path = FeeSchedule::get_path();
+
+        let content = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read fees config : {} , error : {}", path, e));
+
+        let schedule: FeeSchedule = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("Failed to parse fees config : {} , error : {}", path, e));
+
+        if let Err(e) = schedule.validate() {
+            panic!("Invalid fees config : {} , error : {}", path, e);
+        }
+
+        schedule
+    }
+
+    fn validate(&self) -> Result<(), String> {
+
+        if self.fee_account.is_empty() {
+            return Err(String::from("fee_account must be set"));
+        }
+
+        if !self.tiers.contains_key(&self.default_tier) {
+            return Err(format!("default tier {} is not configured", self.default_tier));
+        }
+
+        for (name, tier) in self.tiers.iter() {
+
+            if tier.taker_rate < Decimal::ZERO || tier.taker_rate >= Decimal::ONE {
+                return Err(format!("taker_rate of tier {} must be between 0 and 1", name));
+            }
+
+            if tier.maker_rate <= -Decimal::ONE || tier.maker_rate >= Decimal::ONE {
+                return Err(format!("maker_rate of tier {} must be between -1 and 1", name));
+            }
+        }
+
+        for (user_id, tier) in self.user_tiers.iter() {
+            if !self.tiers.contains_key(tier) {
+                return Err(format!("tier {} of user {} is not configured", tier, user_id));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// tier of the user, the default tier if the user has no override
+    pub fn get_tier(&self, user_id: &str) -> FeeTier {
+        self.user_tiers.get(user_id)
+        .and_then(|tier| self.tiers.get(tier))
+        .or_else(|| self.tiers.get(&self.default_tier))
+        .copied()
+        .unwrap_or_default()
+    }
+}
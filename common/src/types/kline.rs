@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::order::{Price, Quantity};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KlineInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl KlineInterval {
+
+    pub const ALL: [KlineInterval; 5] = [
+        KlineInterval::OneMinute,
+        KlineInterval::FiveMinutes,
+        KlineInterval::FifteenMinutes,
+        KlineInterval::OneHour,
+        KlineInterval::OneDay,
+    ];
+
+    pub fn get_duration_ms(&self) -> i64 {
+        let minute = 60 * 1000;
+
+        match self {
+            KlineInterval::OneMinute => minute,
+            KlineInterval::FiveMinutes => 5 * minute,
+            KlineInterval::FifteenMinutes => 15 * minute,
+            KlineInterval::OneHour => 60 * minute,
+            KlineInterval::OneDay => 24 * 60 * minute,
+        }
+    }
+
+    /// start of the kline the timestamp falls in, klines are aligned to the unix epoch
+    pub fn get_open_time(&self, timestamp: i64) -> i64 {
+        timestamp - timestamp.rem_euclid(self.get_duration_ms())
+    }
+}
+
+impl Display for KlineInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OneMinute => write!(f, "1m"),
+            Self::FiveMinutes => write!(f, "5m"),
+            Self::FifteenMinutes => write!(f, "15m"),
+            Self::OneHour => write!(f, "1h"),
+            Self::OneDay => write!(f, "1d"),
+        }
+    }
+}
+
+/// OHLCV bar of the trades of a market in an interval,
+/// open and close times are in milliseconds and both inclusive
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Kline {
+    pub market: String,
+    pub interval: KlineInterval,
+    pub open_time: i64,
+    pub close_time: i64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Quantity,
+    pub quote_volume: Quantity,
+    pub trade_count: i64,
+}
+
+impl Kline {
+
+    /// kline opened by the trade
+    pub fn new(market:&str, interval:KlineInterval, timestamp:i64, price:Price, quantity:Quantity, quote_qty:Quantity) -> Self {
+
+        let open_time = interval.get_open_time(timestamp);
+
+        Self {
+            market: market.to_string(),
+            interval,
+            open_time,
+            close_time: open_time + interval.get_duration_ms() - 1,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: quantity,
+            quote_volume: quote_qty,
+            trade_count: 1,
+        }
+    }
+
+    pub fn add_trade(&mut self, price:Price, quantity:Quantity, quote_qty:Quantity) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += quantity;
+        self.quote_volume += quote_qty;
+        self.trade_count += 1;
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::types::order::{Price, Quantity};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketConfig {
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub tick_size: Price,
+    pub lot_size: Quantity,
+    pub min_quantity: Quantity,
+    pub max_quantity: Quantity,
+    /// minimum value of an order in quote asset
+    pub min_notional: Decimal,
+    pub enabled: bool,
+}
+
+impl MarketConfig {
+    // ex: SOL_USDC
+    pub fn get_market(&self) -> String {
+        format!("{}_{}", self.base_asset, self.quote_asset)
+    }
+}
+
+/// Markets supported by the exchange,
+/// loaded from the json file at `MARKETS_CONFIG`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketRegistry {
+    pub markets: Vec<MarketConfig>,
+}
+
+impl MarketRegistry {
+
+    pub fn get_path() -> String {
+        std::env::var("MARKETS_CONFIG").unwrap_or_else(|_|String::from("markets.json"))
+    }
+
+    /// panics if the config can't be read or is invalid,
+    /// as nothing can be served without the markets
+    pub fn load() -> Self {
+        let path = MarketRegistry::get_path();
+
+        let content = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read markets config : {} , error : {}", path, e));
+
+        let registry: MarketRegistry = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("Failed to parse markets config : {} , error : {}", path, e));
+
+        if let Err(e) = registry.validate() {
+            panic!("Invalid markets config : {} , error : {}", path, e);
+        }
+
+        registry
+    }
+
+    fn validate(&self) -> Result<(), String> {
+
+        let mut markets = vec![];
+        let mut asset_decimals: HashMap<&str, u8> = HashMap::new();
+
+        for config in self.markets.iter() {
+
+            let market = config.get_market();
+
+            if markets.contains(&market) {
+                return Err(format!("market {} is configured more than once", market));
+            }
+
+            if config.tick_size <= Decimal::ZERO || config.lot_size <= Decimal::ZERO {
+                return Err(format!("tick_size and lot_size of {} must be greater than 0", market));
+            }
+
+            if config.min_quantity <= Decimal::ZERO || config.max_quantity < config.min_quantity {
+                return Err(format!("min_quantity of {} must be greater than 0 and less than max_quantity", market));
+            }
+
+            // so the order amounts are always whole lamports and never get truncated
+            if config.lot_size.normalize().scale() > u32::from(config.base_decimals) {
+                return Err(format!("lot_size of {} has more decimals than {}", market, config.base_asset));
+            }
+
+            if config.tick_size.normalize().scale() + config.lot_size.normalize().scale() > u32::from(config.quote_decimals) {
+                return Err(format!("tick_size * lot_size of {} has more decimals than {}", market, config.quote_asset));
+            }
+
+            let assets = [
+                (config.base_asset.as_str(), config.base_decimals),
+                (config.quote_asset.as_str(), config.quote_decimals),
+            ];
+
+            for (asset, decimals) in assets {
+                match asset_decimals.get(asset) {
+                    Some(existing) if *existing != decimals => {
+                        return Err(format!("{} has decimals {} and {} in different markets", asset, existing, decimals));
+                    },
+                    _ => {
+                        asset_decimals.insert(asset, decimals);
+                    }
+                }
+            }
+
+            markets.push(market);
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, market: &str) -> Option<&MarketConfig> {
+        self.markets.iter().find(|config| config.get_market() == market)
+    }
+
+    /// decimals of every asset traded on the exchange
+    pub fn get_asset_decimals(&self) -> HashMap<String, u8> {
+        let mut asset_decimals = HashMap::new();
+
+        for config in self.markets.iter() {
+            asset_decimals.insert(config.base_asset.clone(), config.base_decimals);
+            asset_decimals.insert(config.quote_asset.clone(), config.quote_decimals);
+        }
+
+        asset_decimals
+    }
+}
@@ -1,2 +1,6 @@
 pub mod order;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod market;
+pub mod fee;
+pub mod kline;
+pub mod ticker;
\ No newline at end of file
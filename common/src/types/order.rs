@@ -9,11 +9,18 @@ pub type Quantity = Decimal;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fill{
     pub order_id: String,
-    pub trade_id: u32,
+    pub trade_id: u64,
     pub quantity: Quantity,
     pub filled_quantity: Quantity,
+    // filled quantity of the maker order after the fill
+    pub order_filled_quantity: Quantity,
     pub maker_id: String,
-    pub price: Price
+    pub price: Price,
+    // fees are charged in the asset received, a negative maker fee is a rebate
+    pub taker_fee: Decimal,
+    pub taker_fee_asset: String,
+    pub maker_fee: Decimal,
+    pub maker_fee_asset: String,
 }
 
 #[derive(Deserialize, Debug, Clone, Copy, Serialize, PartialEq, PartialOrd)]
@@ -35,4 +42,40 @@ impl Display for OrderSide {
 pub enum OrderType{
     Limit,
     Market,
+    // trigger orders wait in the trigger book till the last price reaches their trigger price,
+    // then they are placed as a market or a limit order
+    StopMarket,
+    StopLimit,
+    TakeProfitMarket,
+    TakeProfitLimit,
+}
+
+impl Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Limit => write!(f, "Limit"),
+            Self::Market => write!(f, "Market"),
+            Self::StopMarket => write!(f, "StopMarket"),
+            Self::StopLimit => write!(f, "StopLimit"),
+            Self::TakeProfitMarket => write!(f, "TakeProfitMarket"),
+            Self::TakeProfitLimit => write!(f, "TakeProfitLimit"),
+        }
+    }
+}
+
+/// How long an order stays active before its unfilled part is cancelled
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TimeInForce{
+    // good till cancelled, the unfilled part sits on the book
+    #[default]
+    #[serde(rename = "GTC")]
+    Gtc,
+    // immediate or cancel, the unfilled part is cancelled
+    #[serde(rename = "IOC")]
+    Ioc,
+    // fill or kill, the order is rejected unless it can be filled completely
+    #[serde(rename = "FOK")]
+    Fok,
+    // rejected if it would match any order on the book, so it only ever adds liquidity
+    PostOnly,
 }
\ No newline at end of file
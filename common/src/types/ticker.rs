@@ -0,0 +1,26 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::types::order::{Price, Quantity};
+
+/// Statistics of the trades of a market in the last 24 hours and its best prices
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ticker {
+    pub market: String,
+    pub last_price: Price,
+    // price of the first trade in the last 24 hours
+    pub open_price: Price,
+    pub high_price: Price,
+    pub low_price: Price,
+    pub volume: Quantity,
+    pub quote_volume: Quantity,
+    pub price_change: Price,
+    pub price_change_percent: Decimal,
+    pub trade_count: i64,
+    pub best_bid_price: Option<Price>,
+    pub best_bid_quantity: Option<Quantity>,
+    pub best_ask_price: Option<Price>,
+    pub best_ask_quantity: Option<Quantity>,
+    // the 24 hours end at this time, in milliseconds
+    pub timestamp: i64,
+}
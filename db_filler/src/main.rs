@@ -10,7 +10,7 @@ async fn main() {
     dotenv().ok();
 
     let mut redis_service = RedisService::new().await;
-    let db_manager = DbManager::new().await;
+    let mut db_manager = DbManager::new().await;
 
     println!("starting db_filler");
 
@@ -18,7 +18,7 @@ async fn main() {
         let message = redis_service.get_message_from_engine().await;
         if let Some(msg) = message {
             println!("received msg : {}", msg);
-            db_manager.process_message(&msg).await;
+            db_manager.process_message(&msg, &mut redis_service).await;
         }        
     }
     
@@ -1,9 +1,12 @@
 use common::message::db_filler::DbFillerMessage;
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
-use store::{Order, UpdateDbOrder};
+use store::{LedgerEntry, Order, UpdateDbOrder};
+
+use crate::services::{kline::KlineAggregator, redis::RedisService};
 
 pub struct DbManager{
-    pool: Pool<Postgres>
+    pool: Pool<Postgres>,
+    kline_aggregator: KlineAggregator,
 }
 
 impl DbManager{
@@ -20,15 +23,16 @@ impl DbManager{
         println!("DB connection successfull");
 
         Self {
-            pool
+            pool,
+            kline_aggregator: KlineAggregator::new(),
         }
     }
 
-    pub async fn process_message(&self, message:&str){
+    pub async fn process_message(&mut self, message:&str, redis:&mut RedisService){
         
         let message_res =  DbFillerMessage::get_deserialized(message);
         
-        if let None = message_res {
+        if message_res.is_none() {
             println!("cannot deserialize messgae : {}", message);
             return;
         }
@@ -43,7 +47,6 @@ impl DbManager{
             } => {
 
                 let mut add_res = None;
-                let update_res;
 
                 let update_db_orders: Vec<UpdateDbOrder> = update_orders.into_iter().map(|order|{
 
@@ -51,6 +54,7 @@ impl DbManager{
                         order_id: order.order_id, 
                         filled_quantity:order.filled_quantity.to_string(), 
                         status: order.status.to_string(), 
+                        updated_at: order.timestamp,
                     }
                 }).collect();
 
@@ -63,6 +67,11 @@ impl DbManager{
                         price: order.price.to_string(),
                         quantity: order.quantity.to_string(),
                         side:order.side.to_string(),
+                        user_id: order.user_id,
+                        market: order.market,
+                        order_type: order.order_type.to_string(),
+                        created_at: order.timestamp,
+                        updated_at: order.timestamp,
                     };
 
                     let res = Order::add_order(parsed_order, &self.pool);
@@ -72,7 +81,7 @@ impl DbManager{
                     println!("add_order is NONE, only update orders will be executed");
                 }
 
-                update_res = Order::update_orders(update_db_orders, &self.pool);
+                let update_res = Order::update_orders(update_db_orders, &self.pool);
 
 
                 if let Some(val) = add_res {
@@ -90,34 +99,66 @@ impl DbManager{
             },
             DbFillerMessage::AddTrade(trades) => {
 
-                let parsed_trades: Vec<store::Trade> = trades.into_iter().map(|trade| {
+                let parsed_trades: Vec<store::Trade> = trades.iter().map(|trade| {
 
-                    let add_trade = store::Trade {
-                        id : i64::from(trade.id),
-                        market: trade.market,
-                        matched_at: i64::from(trade.timestamp),
+                    store::Trade {
+                        id : trade.id as i64,
+                        market: trade.market.clone(),
+                        matched_at: trade.timestamp,
                         price: trade.price.to_string(),
                         quantity: trade.quantity.to_string(),
                         quote_qty: trade.quote_qty.to_string(),
-                    };
-
-                    add_trade
+                        maker_fee: trade.maker_fee.to_string(),
+                        maker_fee_asset: trade.maker_fee_asset.clone(),
+                        taker_fee: trade.taker_fee.to_string(),
+                        taker_fee_asset: trade.taker_fee_asset.clone(),
+                        maker_order_id: trade.maker_order_id.clone(),
+                        taker_order_id: trade.taker_order_id.clone(),
+                        maker_user_id: trade.maker_user_id.clone(),
+                        taker_user_id: trade.taker_user_id.clone(),
+                        is_buyer_maker: trade.is_buyer_maker,
+                    }
 
                 }).collect();
 
-                let res = store::Trade::add_trades(parsed_trades, &self.pool).await;
+                let added = match store::Trade::add_trades(parsed_trades, &self.pool).await {
+                    Ok(added) => added,
+                    Err(e) => {
+                        println!("Error while adding trade : {}", e);
+                        return;
+                    }
+                };
+
+                // trades published again by the engine are already in the klines
+                let new_trades: Vec<_> = trades.into_iter()
+                .filter(|trade| added.iter().any(|(market, id)| *market == trade.market && *id == trade.id as i64))
+                .collect();
 
-                if let Err(e) = res{
-                    println!("Error while adding trade : {}", e);
-                }
+                self.kline_aggregator.add_trades(&new_trades, &self.pool, redis).await;
 
             },
-            DbFillerMessage::UpdateCancelOrders(orders) => {
+            DbFillerMessage::UpdateCancelOrders { user_id, order_ids, timestamp } => {
 
-                let res = Order::update_cancelled_orders(orders, &self.pool).await;
+                let res = Order::update_cancelled_orders(&user_id, order_ids, timestamp, &self.pool).await;
                 if let Err(e) = res {
                     println!("Error while cancelling orders: {}", e);
                 }
+            },
+            DbFillerMessage::AddLedgerEntry(entry) => {
+
+                let ledger_entry = LedgerEntry {
+                    id: entry.id as i64,
+                    user_id: entry.user_id,
+                    asset: entry.asset,
+                    amount: entry.amount.to_string(),
+                    entry_type: entry.entry_type.to_string(),
+                    created_at: entry.timestamp,
+                };
+
+                let res = LedgerEntry::add_entry(ledger_entry, &self.pool).await;
+                if let Err(e) = res {
+                    println!("Error while adding ledger entry : {}", e);
+                }
             }
         }
 
@@ -0,0 +1,127 @@
+use std::{collections::HashMap, str::FromStr};
+
+use common::{message::{db_filler::Trade, ws::WsMessage}, types::kline::{Kline, KlineInterval}};
+use rust_decimal::Decimal;
+use sqlx::{Pool, Postgres};
+
+use crate::services::redis::RedisService;
+
+/// Aggregates the trades into the klines of every interval.
+/// The latest kline of each market and interval is kept in memory, and is
+/// read from the db after a restart, as the trades reach here in the order they were matched.
+pub struct KlineAggregator {
+    latest_klines: HashMap<(String, KlineInterval), Kline>,
+}
+
+impl KlineAggregator {
+
+    pub fn new() -> Self {
+        Self {
+            latest_klines: HashMap::new(),
+        }
+    }
+
+    /// updates the klines of the trades, then saves and publishes every updated kline once
+    pub async fn add_trades(&mut self, trades:&[Trade], pool:&Pool<Postgres>, redis:&mut RedisService){
+
+        let mut updated_keys = vec![];
+
+        for trade in trades {
+            for interval in KlineInterval::ALL {
+
+                let key = (trade.market.clone(), interval);
+
+                self.add_trade(&key, trade, pool).await;
+
+                if !updated_keys.contains(&key) {
+                    updated_keys.push(key);
+                }
+            }
+        }
+
+        for key in updated_keys {
+
+            let kline = match self.latest_klines.get(&key) {
+                Some(kline) => kline,
+                None => continue,
+            };
+
+            if let Err(e) = store::Kline::upsert_kline(KlineAggregator::to_db_kline(kline), pool).await {
+                println!("Error while saving kline : {} {} , error : {}", kline.market, kline.interval, e);
+            }
+
+            let channel = format!("kline_{}@{}", kline.interval, kline.market);
+            redis.publish_to_ws(&channel, WsMessage::Kline(kline.clone())).await;
+        }
+    }
+
+    async fn add_trade(&mut self, key:&(String, KlineInterval), trade:&Trade, pool:&Pool<Postgres>){
+
+        let (market, interval) = key;
+        let open_time = interval.get_open_time(trade.timestamp);
+
+        // the kline of the trade could be saved before a restart
+        if !self.latest_klines.contains_key(key) {
+            if let Some(kline) = KlineAggregator::get_saved_kline(market, *interval, open_time, pool).await {
+                self.latest_klines.insert(key.clone(), kline);
+            }
+        }
+
+        match self.latest_klines.get_mut(key) {
+            Some(kline) if kline.open_time == open_time => {
+                kline.add_trade(trade.price, trade.quantity, trade.quote_qty);
+            },
+            _ => {
+                let kline = Kline::new(market, *interval, trade.timestamp, trade.price, trade.quantity, trade.quote_qty);
+                self.latest_klines.insert(key.clone(), kline);
+            }
+        }
+    }
+
+    async fn get_saved_kline(market:&str, interval:KlineInterval, open_time:i64, pool:&Pool<Postgres>) -> Option<Kline> {
+
+        let res = store::Kline::get_kline(market, &interval.to_string(), open_time, pool).await;
+
+        match res {
+            Ok(Some(kline)) => {
+
+                let parse = |value:&str| Decimal::from_str(value).unwrap_or_default();
+
+                Some(Kline {
+                    market: kline.market,
+                    interval,
+                    open_time: kline.open_time,
+                    close_time: kline.close_time,
+                    open: parse(&kline.open),
+                    high: parse(&kline.high),
+                    low: parse(&kline.low),
+                    close: parse(&kline.close),
+                    volume: parse(&kline.volume),
+                    quote_volume: parse(&kline.quote_volume),
+                    trade_count: kline.trade_count,
+                })
+            },
+            Ok(None) => None,
+            Err(e) => {
+                println!("Error while getting kline : {} {} , error : {}", market, interval, e);
+                None
+            }
+        }
+    }
+
+    fn to_db_kline(kline:&Kline) -> store::Kline {
+        store::Kline {
+            market: kline.market.clone(),
+            interval: kline.interval.to_string(),
+            open_time: kline.open_time,
+            close_time: kline.close_time,
+            open: kline.open.normalize().to_string(),
+            high: kline.high.normalize().to_string(),
+            low: kline.low.normalize().to_string(),
+            close: kline.close.normalize().to_string(),
+            volume: kline.volume.normalize().to_string(),
+            quote_volume: kline.quote_volume.normalize().to_string(),
+            trade_count: kline.trade_count,
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod redis;
-pub mod db;
\ No newline at end of file
+pub mod db;
+pub mod kline;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use common::channel::DB_CHANNEL;
+use common::{channel::DB_CHANNEL, message::ws::WsMessage};
 use redis::{aio::MultiplexedConnection, AsyncTypedCommands};
 
 pub struct RedisService {
@@ -38,4 +38,19 @@ impl RedisService {
             }
         }
     }
+
+    pub async fn publish_to_ws(&mut self, channel:&str, message:WsMessage){
+
+        let serialized = match serde_json::to_string(&message) {
+            Ok(serialized) => serialized,
+            Err(e) => {
+                println!("error while serializing message for wss : {} ", e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.conn.publish(channel, serialized).await {
+            println!("Error:{} while publishing to wss", e);
+        }
+    }
 }
\ No newline at end of file
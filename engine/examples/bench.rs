@@ -0,0 +1,154 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use common::{message::api::{CancelOrderPayload, CreateOrderPayload}, types::{fee::FeeSchedule, market::MarketRegistry, order::{OrderSide, OrderType, Price, Quantity}}};
+use rust_decimal::{dec, Decimal};
+
+use engine::{engine::{AssetBalance, UserAssetBalance}, order::Order, orderbook::OrderBook};
+
+const MAKER: &str = "bench_maker";
+const TAKER: &str = "bench_taker";
+
+/// Times placing, matching and cancelling orders on a book of the given size.
+/// The engine logs every order on stdout, so the results are written to stderr,
+/// run it as `cargo run --release -p engine --example bench -- <levels> <orders_per_level> <iterations> > /dev/null`
+fn main() {
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let levels: usize = args.first().and_then(|arg| arg.parse().ok()).unwrap_or(1000);
+    let orders_per_level: usize = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(1);
+    let iterations: usize = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(1000);
+
+    let registry = MarketRegistry::load();
+    let config = registry.markets.first().expect("No market in the registry to bench");
+
+    let mut orderbook = OrderBook::new(config, &FeeSchedule::default());
+    let user_balances = get_user_balances(&orderbook);
+
+    let tick_size = orderbook.tick_size;
+    // keeps the deepest bid above the min notional
+    let mid_price = dec!(100) + tick_size * Decimal::from(levels);
+    let quantity = dec!(1);
+
+    eprintln!(
+        "Bench on {} with {} levels on each side, {} orders per level and {} iterations",
+        orderbook.market, levels, orders_per_level, iterations
+    );
+
+    // resting orders, bids below and asks above the mid price
+    let mut resting_ids = vec![];
+    let start = Instant::now();
+
+    for level in 0..levels {
+
+        let offset = tick_size * Decimal::from(level + 1);
+
+        for index in 0..orders_per_level {
+
+            for (side, price) in [(OrderSide::Buy, mid_price - offset), (OrderSide::Sell, mid_price + offset)] {
+
+                let id = format!("rest_{:?}_{}_{}", side, level, index);
+                place(&mut orderbook, &id, MAKER, side, price, quantity, &user_balances);
+
+                // the best ask is filled by the takers
+                if level > 0 {
+                    resting_ids.push(id);
+                }
+            }
+        }
+    }
+
+    print_result("place resting order", start.elapsed(), levels * orders_per_level * 2);
+
+    // every taker fills the best ask, which is placed back for the next one
+    let best_ask = mid_price + tick_size;
+    let mut matching = Duration::ZERO;
+
+    for iteration in 0..iterations {
+
+        let start = Instant::now();
+        place(&mut orderbook, &format!("taker_{}", iteration), TAKER, OrderSide::Buy, best_ask, quantity, &user_balances);
+        matching += start.elapsed();
+
+        place(&mut orderbook, &format!("refill_{}", iteration), MAKER, OrderSide::Sell, best_ask, quantity, &user_balances);
+    }
+
+    print_result("match taker order", matching, iterations);
+
+    // resting orders spread over the whole book
+    let step = (resting_ids.len() / iterations.max(1)).max(1);
+    let mut cancelled = 0;
+    let start = Instant::now();
+
+    for id in resting_ids.iter().step_by(step).take(iterations) {
+
+        let payload = CancelOrderPayload {
+            id: id.clone(),
+            market: orderbook.market.clone(),
+            order_id: id.clone(),
+            user_id: MAKER.to_string(),
+        };
+
+        if let Err(e) = orderbook.cancel_order(payload, Arc::clone(&user_balances)) {
+            panic!("Failed to cancel order : {} in bench , error : {}", id, e);
+        }
+
+        cancelled += 1;
+    }
+
+    print_result("cancel order", start.elapsed(), cancelled);
+}
+
+fn place(
+    orderbook: &mut OrderBook,
+    id: &str,
+    user_id: &str,
+    side: OrderSide,
+    price: Price,
+    quantity: Quantity,
+    user_balances: &Arc<Mutex<UserAssetBalance>>,
+){
+    let payload = CreateOrderPayload {
+        id: id.to_string(),
+        user_id: user_id.to_string(),
+        side,
+        market: orderbook.market.clone(),
+        order_type: OrderType::Limit,
+        price: Some(price),
+        quantity: Some(quantity),
+        quote_quantity: None,
+        time_in_force: Default::default(),
+        trigger_price: None,
+    };
+
+    let mut order = Order::from_create_order_payload(payload);
+
+    if let Err(e) = orderbook.process_order(&mut order, Arc::clone(user_balances)) {
+        panic!("Failed to place order : {} in bench , error : {}", id, e);
+    }
+}
+
+/// balances which never run out during the bench
+fn get_user_balances(orderbook: &OrderBook) -> Arc<Mutex<UserAssetBalance>> {
+
+    let mut user_balances: UserAssetBalance = HashMap::new();
+
+    for user_id in [MAKER, TAKER] {
+
+        let mut balances = HashMap::new();
+
+        for asset in [&orderbook.base_asset, &orderbook.quote_asset] {
+            balances.insert(asset.clone(), AssetBalance { available_amount: u64::MAX / 4, locked_amount: 0 });
+        }
+
+        user_balances.insert(user_id.to_string(), balances);
+    }
+
+    Arc::new(Mutex::new(user_balances))
+}
+
+fn print_result(operation: &str, elapsed: Duration, count: usize) {
+
+    let per_operation = elapsed.as_secs_f64() * 1_000_000.0 / count.max(1) as f64;
+
+    eprintln!("{:<20} : {:>8} in {:>10.2} ms , {:>10.2} us per order", operation, count, elapsed.as_secs_f64() * 1000.0, per_operation);
+}
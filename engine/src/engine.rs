@@ -1,50 +1,104 @@
 use std::{collections::HashMap, sync::{mpsc, Arc, Mutex}};
-use common::message::api::MessageFromApi;
+use chrono::Utc;
+use common::{message::api::MessageFromApi, types::{fee::FeeSchedule, market::MarketRegistry}};
+use serde::{Deserialize, Serialize};
 
-use crate::orderbook::OrderBook;
+use crate::{errors::EngineError, journal::{Journal, JournalEntry, JournalMessage}, orderbook::OrderBook, services::redis::RedisService, snapshot::Snapshot, user::User};
 
 // TODO: SEPARATE IT FROM USER AND ORDER RELATED STUFFS
-pub type MarketTx = mpsc::Sender<MessageFromApi>;
+pub type MarketTx = mpsc::Sender<MarketMessage>;
 
 pub type UserAssetBalance = HashMap<String, HashMap<String, AssetBalance>>;
 
+/// Messages sent from main to the orderbook threads
+pub enum MarketMessage {
+    /// journaled message, the orderbook sends its seq back on the given tx once it is applied.
+    /// the tx is dropped without sending if the orderbook thread panics while applying it
+    Api(JournalEntry, mpsc::SyncSender<u64>),
+    /// message which doesn't change the orderbook, it is not journaled
+    Query(MessageFromApi),
+    /// orderbook sends a copy of itself back on the given tx,
+    /// after processing all the messages received before
+    Snapshot(mpsc::Sender<OrderBook>),
+}
+
 #[derive(Clone)]
 pub struct Engine {
     pub orderbooks: Vec<OrderBook>,
     pub user_balances: UserAssetBalance,
+    // sequence of the last journal entry applied on the orderbooks
+    pub last_seq: u64,
 }
 
 
 impl Engine {
 
-    // TODO: RECOVER SNAPSHOT 
-    // AND PERIODICALLY SAVE THE SNAPSHOT
-    pub fn init() -> Self{
+    /// recovers the orderbooks and balances from the snapshot if present,
+    /// else starts with empty orderbooks and the dummy users.
+    /// markets added to the registry after the snapshot start with empty orderbooks.
+    pub fn init(registry: &MarketRegistry, fee_schedule: &FeeSchedule, snapshot: Option<Snapshot>) -> Self{
 
-        let assets = [
-            ("SOL".to_string(),9),
-            ("BONK".to_string(),8),
-            ("JUP".to_string(), 6)
-        ];
+        let (mut recovered_orderbooks, mut user_balances, last_seq) = match snapshot {
+            Some(snapshot) => {
+                println!("Restoring {} orderbooks and {} users from snapshot", snapshot.orderbooks.len(), snapshot.user_balances.len());
+                (snapshot.orderbooks, snapshot.user_balances, snapshot.last_seq)
+            },
+            None => {
+                // Initially all the balances will be zero
+                let mut balances: UserAssetBalance = HashMap::new();
+
+                Engine::set_base_balance(registry, &mut balances);
+
+                (vec![], balances, 0)
+            }
+        };
 
         let mut orderbooks = vec![];
 
-        for (asset, decimals) in assets {
-            orderbooks.push(OrderBook::new(asset.clone(),decimals));
+        for config in registry.markets.iter() {
+
+            let market = config.get_market();
+
+            let recovered = recovered_orderbooks.iter()
+            .position(|orderbook| orderbook.market == market)
+            .map(|index| recovered_orderbooks.remove(index));
+
+            let orderbook = match recovered {
+                Some(mut orderbook) => {
+                    orderbook.apply_config(config);
+                    orderbook.rebuild_order_index();
+                    orderbook.fee_schedule = fee_schedule.clone();
+                    orderbook
+                },
+                None => OrderBook::new(config, fee_schedule),
+            };
+
+            println!("Market : {} enabled : {}", market, orderbook.enabled);
+
+            orderbooks.push(orderbook);
+        }
+
+        // keep the orderbooks removed from the registry, so their resting orders can still be cancelled
+        for mut orderbook in recovered_orderbooks {
+            println!("Market : {} is not in the registry, disabling it", orderbook.market);
+            orderbook.enabled = false;
+            orderbook.fee_schedule = fee_schedule.clone();
+            orderbook.rebuild_order_index();
+            orderbooks.push(orderbook);
         }
 
-        // Initially all the balances will be zero
-        let balances: UserAssetBalance = HashMap::new();
+        // fees are collected here, it can be queried like any other user
+        user_balances.entry(fee_schedule.fee_account.clone()).or_default();
 
         Self { 
             orderbooks, 
-            user_balances: balances, 
+            user_balances, 
+            last_seq,
         }
     }
 
     /// sets the initial balance for dummy users
-    pub fn set_base_balance(user_balances:Arc<Mutex<UserAssetBalance>>){
-        let mut guard = user_balances.lock().unwrap();
+    pub fn set_base_balance(registry: &MarketRegistry, user_balances:&mut UserAssetBalance){
 
         let user_ids = [
             "random1".to_string(),
@@ -53,24 +107,18 @@ impl Engine {
         ];
 
         for user_id in user_ids {
-            guard.insert(user_id.clone(), HashMap::new());
-
-            let assets = [
-                ("SOL".to_string(),9),
-                ("BONK".to_string(),8),
-                ("JUP".to_string(), 6),
-                ("USDC".to_string(), 6)
+            user_balances.insert(user_id.clone(), HashMap::new());
 
-            ];
+            let assets = registry.get_asset_decimals();
 
             for (asset, decimal) in assets {
 
                 let base = 10_u64;
-                let lamports = base.pow(decimal);
+                let lamports = base.pow(u32::from(decimal));
 
                 let amount = 10000 * lamports;
 
-                let asset_balance = guard.get_mut(&user_id).unwrap();
+                let asset_balance = user_balances.get_mut(&user_id).unwrap();
 
                 match asset_balance.get_mut(&asset){
                     Some(_balance) => {
@@ -102,19 +150,226 @@ impl Engine {
     }
 
     pub fn deserialize_message(message:&str)->Result<MessageFromApi, serde_json::Error>{
-        let deserialized = serde_json::from_str::<MessageFromApi>(message);
-        deserialized   
+        serde_json::from_str::<MessageFromApi>(message)
+    }
+
+    /// Applies the messages sent to the orderbook thread, till the tx of the market is dropped
+    pub fn run_orderbook(
+        mut orderbook: OrderBook,
+        rx: mpsc::Receiver<MarketMessage>,
+        user_balances: Arc<Mutex<UserAssetBalance>>,
+        redis_service: RedisService,
+    ){
+        // outputs of the journaled messages are published after main releases the journal,
+        // so a slow redis only holds up this orderbook and not every market
+        let (outputs_tx, outputs_rx) = mpsc::channel::<String>();
+        let outputs_recorder = RedisService::recorder(outputs_tx);
+
+        for message in rx {
+
+            match message {
+
+                MarketMessage::Api(entry, applied_tx) => {
+
+                    // only the api messages are dispatched to the orderbooks
+                    if let JournalMessage::Api(message) = entry.message {
+                        orderbook.process(
+                            message, 
+                            entry.timestamp,
+                            Arc::clone(&user_balances),
+                            &outputs_recorder
+                        );
+                    }
+
+                    if let Err(e) = applied_tx.send(entry.seq) {
+                        println!("Error : {} while sending applied seq : {} of the orderbook : {}", e, entry.seq, &orderbook.market);
+                    }
+
+                    for output in outputs_rx.try_iter() {
+                        redis_service.send_recorded(&output);
+                    }
+                },
+                MarketMessage::Query(message) => {
+                    orderbook.process(
+                        message,
+                        Utc::now().timestamp_millis(),
+                        Arc::clone(&user_balances),
+                        &redis_service
+                    );
+                },
+                MarketMessage::Snapshot(snapshot_tx) => {
+
+                    if let Err(e) = snapshot_tx.send(orderbook.clone()) {
+                        println!("Error : {} while sending snapshot of the orderbook : {}", e, &orderbook.market);
+                    }
+                },
+            }
+        }
+
+        println!("Stopping the orderbook : {}", &orderbook.market);
+    }
+
+    /// Journals the message from the api if it changes the orderbook and sends it to its orderbook.
+    /// The journal is held till the orderbook applies it, same as the balance changes of the user thread.
+    /// The orderbooks and the user thread share the balances, so the changes are applied
+    /// one at a time in the order of the journal, which is the order they are replayed in
+    pub fn dispatch(
+        message: &str,
+        journal: &Arc<Mutex<Journal>>,
+        markets_tx: &HashMap<String, MarketTx>,
+        redis_service: &RedisService,
+    ){
+        let message_type = match Engine::deserialize_message(message) {
+            Ok(message_type) => message_type,
+            Err(e) => {
+                println!("Error while deserializing message : {}, error : {}", message, e);
+                return;
+            }
+        };
+
+        let market = message_type.get_market().to_string();
+        let channel_to_publish = &message_type.get_channel_to_publish();
+
+        let tx = match markets_tx.get(&market) {
+            Some(tx) => tx,
+            None => {
+                println!("No tx found for the market : {}", market);
+                redis_service.publish_message_to_api(channel_to_publish, Err(EngineError::InvalidMarket));
+                return;
+            }
+        };
+
+        if !message_type.is_state_change() {
+            if tx.send(MarketMessage::Query(message_type)).is_err() {
+                println!("Error while sending query to the orderbook : {}", market);
+                redis_service.publish_message_to_api(channel_to_publish, Err(EngineError::InternalError));
+            }
+            return;
+        }
+
+        let mut journal_guard = journal.lock().unwrap();
+
+        // write ahead, the message is dispatched only after it is on the disk
+        let entry = match journal_guard.append(JournalMessage::Api(message_type)) {
+            Ok(entry) => entry,
+            Err(e) => {
+                println!("Error : {} while writing message to the journal", e);
+                redis_service.publish_message_to_api(channel_to_publish, Err(EngineError::InternalError));
+                return;
+            }
+        };
+
+        // the orderbook holds the only tx, so the recv fails instead of
+        // blocking forever if the orderbook thread is gone or panics while applying
+        let (applied_tx, applied_rx) = mpsc::sync_channel::<u64>(1);
+
+        let applied = tx.send(MarketMessage::Api(entry, applied_tx)).is_ok() && applied_rx.recv().is_ok();
+
+        drop(journal_guard);
+
+        // the entry is journaled, so it is applied again when the engine is restarted
+        if !applied {
+            println!("Orderbook : {} failed to apply the order, it is applied again on restart", market);
+            redis_service.publish_message_to_api(channel_to_publish, Err(EngineError::InternalError));
+        }
+    }
+
+    /// Collects the orderbooks from every market thread and then the balances.
+    /// Must be called from the thread dispatching the orders while holding the journal,
+    /// so no new orders or balance changes are applied while the snapshot is being taken.
+    pub fn take_snapshot(
+        last_seq: u64,
+        markets_tx: &HashMap<String, MarketTx>,
+        user_balances: &Arc<Mutex<UserAssetBalance>>,
+    ) -> Option<Snapshot>{
+
+        let (tx, rx) = mpsc::channel::<OrderBook>();
+
+        for (market, market_tx) in markets_tx {
+            if let Err(e) = market_tx.send(MarketMessage::Snapshot(tx.clone())) {
+                println!("Error : {} while requesting snapshot from market : {}", e, market);
+                return None;
+            }
+        }
+
+        let mut orderbooks = vec![];
+
+        for _ in 0..markets_tx.len() {
+            match rx.recv() {
+                Ok(orderbook) => orderbooks.push(orderbook),
+                Err(e) => {
+                    println!("Error : {} while receiving orderbook for snapshot", e);
+                    return None;
+                }
+            }
+        }
+
+        // all the orderbooks are idle now, so the balances
+        // are consistent with the locked funds of the resting orders
+        let user_balances = user_balances.lock().unwrap().clone();
+
+        Some(Snapshot::new(last_seq, orderbooks, user_balances))
+    }
+
+    /// Applies the journal entries one after the other on the current thread,
+    /// so replaying the same entries always produces the same outputs.
+    pub fn replay(&mut self, entries: Vec<JournalEntry>, user: &User, redis: &RedisService){
+
+        let user_balances = Arc::new(Mutex::new(std::mem::take(&mut self.user_balances)));
+
+        for entry in entries {
+
+            if entry.seq <= self.last_seq {
+                continue;
+            }
+
+            let message = match entry.message {
+                JournalMessage::Api(message) => message,
+                JournalMessage::User(user_message) => {
+                    user.process(entry.seq, entry.timestamp, user_message, &user_balances, redis);
+                    self.last_seq = entry.seq;
+                    continue;
+                }
+            };
+
+            let market = message.get_market().to_string();
+
+            match self.orderbooks.iter_mut().find(|orderbook| orderbook.market == market) {
+                Some(orderbook) => {
+                    orderbook.process(
+                        message, 
+                        entry.timestamp,
+                        Arc::clone(&user_balances), 
+                        redis
+                    );
+                },
+                None => {
+                    println!("No orderbook found for the market : {}", market);
+                    redis.publish_message_to_api(&message.get_channel_to_publish(), Err(EngineError::InvalidMarket));
+                }
+            }
+
+            self.last_seq = entry.seq;
+        }
+
+        self.user_balances = std::mem::take(&mut *user_balances.lock().unwrap());
     }
 
     
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AssetBalance{
     pub available_amount: u64,
     pub locked_amount: u64,
 }
 
+impl Default for AssetBalance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AssetBalance {
     pub fn new() -> Self{
         Self {
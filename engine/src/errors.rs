@@ -6,8 +6,18 @@ use thiserror::Error;
 pub enum EngineError {
     #[error("User Not Found ! Please Signup !")]
     UserNotFound,
+    #[error("There are no orders on the book to fill the market order")]
+    InsufficientLiquidity,
     #[error("The order can't be executed, as it will be filled partially !")]
     PartialOrderFill,
+    #[error("Post only order would match an order on the book")]
+    PostOnlyWouldCross,
+    #[error("Time in force is not supported for this order")]
+    InvalidTimeInForce,
+    #[error("Trigger price must be a multiple of the tick size and not already reached by the last price")]
+    InvalidTriggerPrice,
+    #[error("Market trigger orders must be sized by quote quantity to buy and by quantity to sell")]
+    InvalidTriggerOrder,
     #[error("Given user does not have permission to perform the action")]
     MismatchUser,
     #[error("Enter valid order_id")]
@@ -16,13 +26,43 @@ pub enum EngineError {
     InternalError,
     #[error("User does not have sufficient balance")]
     InsufficientBalance,
+    #[error("The balance is locked in open orders, cancel them to withdraw")]
+    FundsLocked,
+    #[error("User already exists")]
+    UserAlreadyExists,
+    #[error("Please Enter Valid Asset")]
+    InvalidAsset,
+    #[error("Amount must be greater than 0 and within the decimals of the asset")]
+    InvalidAmount,
     #[error("Please Enter Valid Market")]
     InvalidMarket,
+    #[error("Trading is disabled on this market")]
+    MarketDisabled,
+    #[error("Price must be greater than 0")]
+    InvalidPrice,
+    #[error("Quantity must be greater than 0")]
+    InvalidQuantity,
+    #[error("Price must be a multiple of the tick size of the market")]
+    InvalidTickSize,
+    #[error("Quantity must be a multiple of the lot size of the market")]
+    InvalidLotSize,
+    #[error("Quantity is less than the minimum quantity of the market")]
+    BelowMinQuantity,
+    #[error("Quantity is more than the maximum quantity of the market")]
+    AboveMaxQuantity,
+    #[error("Order value is less than the minimum notional of the market")]
+    BelowMinNotional,
+    #[error("Order value is too large to be traded")]
+    AboveMaxNotional,
+    #[error("Quote quantity can only be set on market orders without a quantity, within the decimals of the quote asset")]
+    InvalidQuoteQuantity,
+    #[error("Depth grouping must be a multiple of the tick size of the market")]
+    InvalidDepthGrouping,
 }
 
 impl EngineError {
-    pub fn to_error_response(self:Self) -> ErrorResponse{
-        let error_code = serde_json::to_string(&self).unwrap_or_else(|_|"INTERNAL_ERROR".to_string());
+    pub fn to_error_response(&self) -> ErrorResponse{
+        let error_code = serde_json::to_string(self).unwrap_or_else(|_|"INTERNAL_ERROR".to_string());
         let error_message = self.to_string();
 
         ErrorResponse {
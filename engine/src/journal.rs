@@ -0,0 +1,236 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, BufRead, BufReader, Write}, path::{Path, PathBuf}};
+use chrono::Utc;
+use common::message::api::{MessageFromApi, UserMessageFromApi};
+use serde::{Deserialize, Serialize};
+
+/// Messages which change the state of the engine
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum JournalMessage {
+    Api(MessageFromApi),
+    User(UserMessageFromApi),
+}
+
+/// A message accepted by the engine, stamped with the
+/// sequence and the time it was accepted at
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub timestamp: i64,
+    pub message: JournalMessage,
+}
+
+/// Append only log of every message dispatched to the orderbooks and every balance change,
+/// written before they are applied so that no accepted order or deposit is lost on a crash.
+/// Each line of the file is a serialized `JournalEntry`. The file is rotated on every snapshot,
+/// the rotated files are named with the sequence of their last entry.
+pub struct Journal {
+    file: File,
+    last_seq: u64,
+}
+
+impl Journal {
+
+    pub fn get_path() -> PathBuf {
+        let path = std::env::var("JOURNAL_PATH").unwrap_or_else(|_|String::from("journal/orders.log"));
+        PathBuf::from(path)
+    }
+
+    /// rotated journals with the sequence of their last entry, oldest first
+    fn get_rotated_files(path: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let prefix = match path.file_name() {
+            Some(name) => format!("{}.", name.to_string_lossy()),
+            None => return Ok(vec![]),
+        };
+
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut files = vec![];
+
+        for entry in read_dir {
+            let file_path = entry?.path();
+
+            let seq = file_path.file_name()
+            .and_then(|name| name.to_string_lossy().strip_prefix(&prefix).and_then(|seq| seq.parse::<u64>().ok()));
+
+            if let Some(seq) = seq {
+                files.push((seq, file_path));
+            }
+        }
+
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// returns the valid entries in the journal and the length of the file till the last valid entry.
+    /// reading stops at the first line which can't be deserialized, as it could only be a
+    /// partially written entry from a crash.
+    fn read(path: &PathBuf) -> io::Result<(Vec<JournalEntry>, u64)> {
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((vec![], 0)),
+            Err(e) => return Err(e),
+        };
+
+        let mut reader = BufReader::new(file);
+        let mut entries = vec![];
+        let mut valid_len = 0_u64;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            let read = reader.read_line(&mut line)?;
+
+            if read == 0 {
+                break;
+            }
+
+            if !line.ends_with('\n') {
+                println!("Ignoring partially written journal entry at offset : {}", valid_len);
+                break;
+            }
+
+            match serde_json::from_str::<JournalEntry>(line.trim_end()) {
+                Ok(entry) => {
+                    entries.push(entry);
+                    valid_len += read as u64;
+                },
+                Err(e) => {
+                    println!("Error : {} while reading journal entry at offset : {}", e, valid_len);
+                    break;
+                }
+            }
+        }
+
+        Ok((entries, valid_len))
+    }
+
+    /// entries with sequence greater than the given seq, in the order they were written,
+    /// from the rotated journals which have them and then the current one
+    pub fn read_entries_after(seq: u64) -> io::Result<Vec<JournalEntry>> {
+
+        let path = Journal::get_path();
+
+        let mut entries = vec![];
+
+        for (last_seq, file) in Journal::get_rotated_files(&path)? {
+            if last_seq > seq {
+                entries.extend(Journal::read(&file)?.0);
+            }
+        }
+
+        entries.extend(Journal::read(&path)?.0);
+
+        let entries = entries.into_iter()
+        .filter(|entry| entry.seq > seq)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// opens the journal for appending, dropping any partially written entry at the end.
+    /// new entries continue from the last sequence in the journal or the given seq, whichever is greater.
+    pub fn open(last_applied_seq: u64) -> io::Result<Self> {
+
+        let path = Journal::get_path();
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let (entries, valid_len) = Journal::read(&path)?;
+
+        let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+
+        // cut the torn write, else the next entry will be appended to it
+        file.set_len(valid_len)?;
+
+        let last_seq = entries.last()
+        .map(|entry| entry.seq)
+        .unwrap_or(0)
+        .max(last_applied_seq);
+
+        println!("Opened journal : {:?} with last seq : {}", path, last_seq);
+
+        Ok(Self { file, last_seq })
+    }
+
+    pub fn get_last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// writes the message to the disk and returns the sequenced entry
+    pub fn append(&mut self, message: JournalMessage) -> io::Result<JournalEntry> {
+
+        let entry = JournalEntry {
+            seq: self.last_seq + 1,
+            timestamp: Utc::now().timestamp_millis(),
+            message,
+        };
+
+        let mut serialized = serde_json::to_string(&entry)?;
+        serialized.push('\n');
+
+        self.file.write_all(serialized.as_bytes())?;
+        self.file.sync_data()?;
+
+        self.last_seq = entry.seq;
+
+        Ok(entry)
+    }
+
+    /// moves the entries written till now to a rotated file and starts an empty journal,
+    /// called after a snapshot with the last seq is saved. only the latest `keep` rotated
+    /// files are kept, enough to recover from any of the snapshots kept on the disk.
+    pub fn rotate(&mut self, keep: usize) -> io::Result<()> {
+
+        // nothing was journaled since the last rotation
+        if self.file.metadata()?.len() == 0 {
+            return Ok(());
+        }
+
+        let path = Journal::get_path();
+
+        let rotated_path = PathBuf::from(format!("{}.{:020}", path.to_string_lossy(), self.last_seq));
+
+        fs::rename(&path, &rotated_path)?;
+
+        self.file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+
+        println!("Rotated journal to : {:?}", rotated_path);
+
+        let files = Journal::get_rotated_files(&path)?;
+
+        if files.len() > keep {
+            for (_, file) in &files[..files.len() - keep] {
+                if let Err(e) = fs::remove_file(file) {
+                    println!("Error : {} while removing old journal : {:?}", e, file);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+pub mod orderbook;
+pub mod engine;
+pub mod errors;
+pub mod journal;
+pub mod order;
+pub mod services;
+pub mod snapshot;
+pub mod ticker;
+pub mod trigger;
+pub mod updates;
+pub mod user;
@@ -1,69 +1,99 @@
-use std::{sync::{mpsc, Arc, Mutex}, thread};
-use common::message::{api::MessageFromApi};
+use std::{fs, sync::{mpsc, Arc, Mutex}, thread};
+use common::types::{fee::FeeSchedule, market::MarketRegistry};
 use dotenv::dotenv;
 
-use crate::{engine::Engine, errors::EngineError, services::redis::RedisService, user::User};
-
-mod orderbook;
-mod engine;
-mod errors;
-mod order;
-mod services;
-mod user;
+use engine::{engine::{Engine, MarketMessage}, journal::Journal, services::redis::RedisService, snapshot::{SnapshotManager, SNAPSHOTS_TO_KEEP}, user::User};
 
 // TOTAL THREADS = 1 MAIN + (1* NO.OF.ORDERBOOKS ) + 1 USER REQ thread 
 
+/// Rebuilds the orderbooks and balances from the latest snapshot and the journal entries after it.
+/// Every output is written to the file instead of redis, one `<COMMAND> <channel> <payload>` per line.
+fn replay(output_path: &str){
+
+    println!("Replaying the journal, outputs will be written to : {}", output_path);
+
+    let registry = MarketRegistry::load();
+    let fee_schedule = FeeSchedule::load();
+    let snapshot_manager = SnapshotManager::new();
+    let mut engine = Engine::init(&registry, &fee_schedule, snapshot_manager.load_latest());
+    let user = User::new(&registry);
+
+    let from_seq = engine.last_seq;
+    let entries = Journal::read_entries_after(from_seq).expect("Failed to read the journal");
+
+    let (tx, rx) = mpsc::channel::<String>();
+
+    // recorder is dropped after replaying, so collecting the outputs ends there
+    engine.replay(entries, &user, &RedisService::recorder(tx));
+
+    let mut outputs: Vec<String> = rx.into_iter().collect();
+    outputs.push(String::new());
+
+    fs::write(output_path, outputs.join("\n")).expect("Failed to write the replay outputs");
+
+    println!("Replayed the journal after seq : {} till seq : {}", from_seq, engine.last_seq);
+}
+
 fn main() {
 
     dotenv().ok();
+
+    // `engine replay <output_file>` replays the journal instead of starting the engine
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).is_some_and(|arg| arg == "replay") {
+        let output_path = args.get(2).map_or("replay_output.log", |path| path.as_str());
+        replay(output_path);
+        return;
+    }
+
     println!("Starting the engine");
 
-    let engine = Engine::init();
+    let registry = MarketRegistry::load();
+    let fee_schedule = FeeSchedule::load();
+    let mut snapshot_manager = SnapshotManager::new();
+
+    let mut engine = Engine::init(&registry, &fee_schedule, snapshot_manager.load_latest());
+    let user = User::new(&registry);
+
+    // messages accepted after the snapshot was taken are applied again. their responses and ws updates
+    // are not published again, the db filler messages are, as they could be lost on a crash.
+    // the db filler ignores the ones it already stored
+    let entries = Journal::read_entries_after(engine.last_seq).expect("Failed to read the journal");
+    println!("Recovering {} journal entries after seq : {}", entries.len(), engine.last_seq);
+    engine.replay(entries, &user, &RedisService::recovering());
+
+    // shared with the user thread, which journals the balance changes
+    let journal = Journal::open(engine.last_seq).expect("Failed to open the journal");
+    let journal = Arc::new(Mutex::new(journal));
+
     let mut markets_tx = Engine::init_market_tx();
     let user_balances = engine.user_balances;
 
     let user_balances = Arc::new(Mutex::new(user_balances));
 
-    Engine::set_base_balance(Arc::clone(&user_balances));
+    for orderbook in engine.orderbooks {
 
-    for mut orderbook in engine.orderbooks {
-
-        let (tx, rx) = mpsc::channel::<MessageFromApi>();
+        let (tx, rx) = mpsc::channel::<MarketMessage>();
 
         markets_tx.insert(orderbook.market.clone(), tx);
 
         let user_balances_clone = Arc::clone(&user_balances);
-        let redis_service = RedisService::new();
 
         println!("Spawning thread for the orderbook : {:?}", &orderbook.market);
 
         thread::spawn(move||{
-            loop{
-                let message = rx.recv();
-
-                match message {
-
-                    Ok(message_type) => {
-
-                        orderbook.process(
-                            message_type, 
-                            user_balances_clone.clone(),
-                            &redis_service
-                        );
-                    },
-                    Err(e) => {
-                        println!("Error when receiving message from main : {:?}", e);
-                    },
-                }
-            }
+            Engine::run_orderbook(orderbook, rx, user_balances_clone, RedisService::new());
         });
     }
 
     println!("Spawning a separate thread for handing non-order-processing requests ...");
 
     let user_redis_service = RedisService::new();
+    let user_balances_clone = Arc::clone(&user_balances);
+    let user_journal = Arc::clone(&journal);
     
-    // this is typically to handle things like user balance, current price queries etc..
+    // this is typically to handle things like user balance, deposits, withdrawals etc..
     thread::spawn(move ||{
         loop {
             let try_message = user_redis_service.get_user_message_from_api();
@@ -72,9 +102,10 @@ fn main() {
                 println!("--------------------------------------------------------");
                 println!("received user message : {}", message);
                 
-                User::process_user_message(
+                user.process_user_message(
                     message, 
-                    Arc::clone(&user_balances),
+                    &user_journal,
+                    Arc::clone(&user_balances_clone),
                     &user_redis_service
                 );
             }
@@ -85,48 +116,33 @@ fn main() {
 
     loop {
 
-        let res = redis_service.get_message_from_api();
-
-        match res {
-
-            Some(message_res) => {
-
-                if let Some(message) = message_res {
+        if snapshot_manager.is_due() {
 
-                    println!("--------------------------------------------------------");
-                    println!("received message : {}", message);
+            let mut journal_guard = journal.lock().unwrap();
 
-                    let deserialized = Engine::deserialize_message(&message);
+            if let Some(snapshot) = Engine::take_snapshot(journal_guard.get_last_seq(), &markets_tx, &user_balances) {
+                match snapshot_manager.save(&snapshot) {
+                    Ok(path) => {
+                        println!("Saved snapshot : {:?}", path);
 
-                    match deserialized {
-
-                        Ok(message_type) => {
-
-                            let market = message_type.get_market();
-                            let channel_to_publish = &message_type.get_channel_to_publish();
+                        // the entries till the snapshot are only needed to recover from the older snapshots
+                        if let Err(e) = journal_guard.rotate(SNAPSHOTS_TO_KEEP) {
+                            println!("Error : {} while rotating the journal", e);
+                        }
+                    },
+                    Err(e) => println!("Error : {} while saving snapshot", e),
+                }
+            }
+        }
 
-                            let tx_res = markets_tx.get(market);
+        let res = redis_service.get_message_from_api();
 
-                            match tx_res {
-                                None => {
-                                    println!("No tx found for the market : {}", market);
-                                    redis_service.publish_message_to_api(channel_to_publish, Err(EngineError::InvalidMarket));
-                                },
-                                Some(tx) => {
-                                    let tx_send_err = format!("Error while sending order to the orderbook : {}",market);
-                                    tx.send(message_type).expect(&tx_send_err);
-                                }
-                            }
+        if let Some(Some(message)) = res {
 
-                        },
-                        Err(e) => {
-                            println!("Error while deserializing message : {}, error : {}", message, e);
-                        }
-                    }
-                }
+            println!("--------------------------------------------------------");
+            println!("received message : {}", message);
 
-            },  
-            None => {},
+            Engine::dispatch(&message, &journal, &markets_tx, &redis_service);
         }
 
     }
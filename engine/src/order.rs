@@ -1,4 +1,4 @@
-use common::{message::api::CreateOrderPayload, types::order::{OrderSide, OrderType, Price, Quantity}};
+use common::{message::api::CreateOrderPayload, types::order::{OrderSide, OrderType, Price, Quantity, TimeInForce}};
 use rust_decimal::dec;
 use serde::{Deserialize, Serialize};
 
@@ -9,8 +9,17 @@ pub struct Order {
     pub side: OrderSide,
     pub market: String,
     pub order_type: OrderType,
+    // 0 for market orders
     pub price: Price,
     pub quantity: Quantity,
+    // set only for market orders sized by the quote amount,
+    // the quantity is then derived from the book before matching
+    #[serde(default)]
+    pub quote_quantity: Option<Quantity>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub trigger_price: Option<Price>,
     pub filled:Quantity,
 }
 
@@ -23,12 +32,27 @@ impl Order {
             side: payload.side, 
             market: payload.market,
             order_type: payload.order_type, 
-            price: payload.price, 
-            quantity: payload.quantity,
+            price: payload.price.unwrap_or(dec!(0)), 
+            quantity: payload.quantity.unwrap_or(dec!(0)),
+            quote_quantity: payload.quote_quantity,
+            time_in_force: payload.time_in_force,
+            trigger_price: payload.trigger_price,
             filled: dec!(0), 
         }
     }
 
+    pub fn is_trigger_order(&self) -> bool {
+        !matches!(self.order_type, OrderType::Limit | OrderType::Market)
+    }
+
+    /// type of the order placed on the book once a trigger order is triggered
+    pub fn get_execution_type(&self) -> OrderType {
+        match self.order_type {
+            OrderType::Limit | OrderType::StopLimit | OrderType::TakeProfitLimit => OrderType::Limit,
+            OrderType::Market | OrderType::StopMarket | OrderType::TakeProfitMarket => OrderType::Market,
+        }
+    }
+
     pub fn get_opposing_side(&self) -> OrderSide{
         match self.side {
             OrderSide::Buy => OrderSide::Sell,
@@ -43,7 +67,7 @@ impl PartialEq for Order {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrdersWithQuantity{
     pub orders: Vec<Order>,
     pub total_quantity: Quantity,
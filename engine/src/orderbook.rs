@@ -1,21 +1,61 @@
-use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}};
-use chrono::Utc;
-use common::{message::{api::{CancelOrderPayload, MessageFromApi}, db_filler::{AddOrderToDb, OrderStatus, Trade, UpdateOrder}, engine::{CancelAllOrders, DepthResponse, MessageFromEngine, OpenOrder, OrderCancelledResponse, OrderFill, OrderPlacedResponse}}, types::order::{Fill, OrderSide, OrderType, Price, Quantity}};
+use std::{collections::{BTreeMap, HashMap, HashSet}, sync::{Arc, Mutex}};
+use common::{message::{api::{CancelOrderPayload, DepthPayload, MessageFromApi}, db_filler::{AddOrderToDb, OrderStatus, Trade, UpdateOrder}, engine::{CancelAllOrders, DepthResponse, MessageFromEngine, OpenOrder, OrderCancelledResponse, OrderFill, OrderPlacedResponse}, ws::{DepthUpdate, OrderUpdate}}, types::{fee::FeeSchedule, market::MarketConfig, order::{Fill, OrderSide, OrderType, Price, Quantity, TimeInForce}}};
 use rust_decimal::{dec, Decimal, prelude::ToPrimitive};
-use crate::{engine::{AssetBalance, UserAssetBalance}, errors::{EngineError}, order::{Order, OrdersWithQuantity}, services::redis::RedisService};
+use serde::{Deserialize, Serialize};
+use crate::{engine::UserAssetBalance, errors::{EngineError}, order::{Order, OrdersWithQuantity}, services::redis::RedisService, ticker::TickerStats, trigger::TriggeredOrder};
 
-const QUOTE:&str = "USDC";
-const QUOTE_LAMPORTS:u64 = 1000_000;
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBook {
     pub base_asset: String,
     pub base_decimals: u8,
+    pub quote_asset: String,
+    pub quote_decimals: u8,
+    // new orders are rejected on a disabled market, resting orders can still be cancelled
+    pub enabled: bool,
+    pub tick_size: Price,
+    pub lot_size: Quantity,
+    pub min_quantity: Quantity,
+    pub max_quantity: Quantity,
+    pub min_notional: Decimal,
     pub market: String,
-    pub trade_id: u32,
-    pub bids: HashMap<Price,OrdersWithQuantity>,    
-    pub asks: HashMap<Price,OrdersWithQuantity>,
+    // id of the last trade on the market, trades are identified by the market and the id.
+    // it is saved in the snapshots, so the ids continue after a restart
+    pub trade_id: u64,
+    // id of the last change to a price level. depth updates carry the ids of their changes and
+    // the depth snapshots the last id, so the ws clients can sync their book and detect gaps
+    #[serde(default)]
+    pub depth_update_id: u64,
+    // price levels in ascending order, so the best bid is the last level and the best ask the first
+    pub bids: BTreeMap<Price,OrdersWithQuantity>,
+    pub asks: BTreeMap<Price,OrdersWithQuantity>,
+    // level of every resting order, so an order is found without scanning the book.
+    // it is rebuilt from the levels when the orderbook is restored from a snapshot
+    #[serde(skip)]
+    pub order_index: HashMap<String, OrderLocation>,
+    // stop and take profit orders waiting for their trigger price, in the order they were placed
+    #[serde(default)]
+    pub trigger_orders: Vec<Order>,
     pub last_price: Price,
+    #[serde(default)]
+    pub ticker_stats: TickerStats,
+    // loaded from the config on every start, so it's not a part of the snapshot
+    #[serde(skip)]
+    pub fee_schedule: FeeSchedule,
+}
+
+/// Order executed on the book along with its fills,
+/// the fills carry the details of the makers which are not sent to the taker
+#[derive(Debug)]
+pub struct ExecutedOrder {
+    pub order_placed: OrderPlacedResponse,
+    pub fills: Vec<Fill>,
+}
+
+/// side and price level of a resting order
+#[derive(Debug, Clone, Copy)]
+pub struct OrderLocation {
+    pub side: OrderSide,
+    pub price: Price,
 }
 
 #[derive(Debug)]
@@ -24,17 +64,24 @@ pub struct CompleteFill {
     pub price: Price,
 }
 
+// BTreeMap keeps the depth updates in the same order on every run
 #[derive(Debug)]
 pub struct PriceWithDepth{
-    pub updated_bids: HashMap<Price,Quantity>,
-    pub updated_asks: HashMap<Price,Quantity>,
+    pub updated_bids: BTreeMap<Price,Quantity>,
+    pub updated_asks: BTreeMap<Price,Quantity>,
+}
+
+impl Default for PriceWithDepth {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PriceWithDepth{
     pub fn new() -> Self{
         Self {
-            updated_asks: HashMap::new(),
-            updated_bids: HashMap::new(),
+            updated_asks: BTreeMap::new(),
+            updated_bids: BTreeMap::new(),
         }
     }
 
@@ -53,145 +100,328 @@ impl PriceWithDepth{
 
 impl OrderBook {
 
-    pub fn new(base_asset:String, base_decimals:u8) -> Self {
+    pub fn new(config:&MarketConfig, fee_schedule:&FeeSchedule) -> Self {
 
-        let bids = HashMap::new();
-        let asks = HashMap::new();
+        let bids = BTreeMap::new();
+        let asks = BTreeMap::new();
 
         // ex: SOL_USDC
-        let market = format!("{}_{}",base_asset, QUOTE);
+        let market = config.get_market();
 
         Self { 
-            base_asset,
+            base_asset: config.base_asset.clone(),
+            base_decimals: config.base_decimals,
+            quote_asset: config.quote_asset.clone(),
+            quote_decimals: config.quote_decimals,
+            enabled: config.enabled,
+            tick_size: config.tick_size,
+            lot_size: config.lot_size,
+            min_quantity: config.min_quantity,
+            max_quantity: config.max_quantity,
+            min_notional: config.min_notional,
             market, 
             bids,
             asks,
-            base_decimals,
+            order_index: HashMap::new(),
+            trigger_orders: vec![],
             last_price:dec!(0),
+            ticker_stats: TickerStats::default(),
+            fee_schedule: fee_schedule.clone(),
             trade_id:0,
+            depth_update_id:0,
+        }
+    }
+
+    /// updates the orderbook recovered from a snapshot with the latest config
+    pub fn apply_config(&mut self, config:&MarketConfig) {
+        self.enabled = config.enabled;
+        self.tick_size = config.tick_size;
+        self.lot_size = config.lot_size;
+        self.min_quantity = config.min_quantity;
+        self.max_quantity = config.max_quantity;
+        self.min_notional = config.min_notional;
+    }
+
+    /// indexes the resting orders of the levels, the index is not a part of the snapshot
+    pub fn rebuild_order_index(&mut self) {
+
+        self.order_index.clear();
+
+        for (side, levels) in [(OrderSide::Buy, &self.bids), (OrderSide::Sell, &self.asks)] {
+            for (price, orders_w_qty) in levels {
+                for order in orders_w_qty.orders.iter() {
+                    self.order_index.insert(order.id.clone(), OrderLocation { side, price: *price });
+                }
+            }
         }
     }
 
     pub fn get_base_lamports(&self) -> u64 {
-        let base: u32 = 10; 
+        let base: u64 = 10; 
         let base_decimals = u32::from(self.base_decimals);
-        let base_lamports = base.pow(base_decimals) as u64;
 
-        base_lamports
+        base.pow(base_decimals)
     }
 
-    pub fn validate_and_lock_user_balance(
+    pub fn get_quote_lamports(&self) -> u64 {
+        let base: u64 = 10; 
+        let quote_decimals = u32::from(self.quote_decimals);
+
+        base.pow(quote_decimals)
+    }
+
+    /// amount in lamports truncated to a whole lamport, None if it doesn't fit in u64
+    pub fn to_lamports(amount:Decimal, lamports:u64) -> Option<u64> {
+        amount.checked_mul(Decimal::from(lamports))?.trunc().to_u64()
+    }
+
+    /// moves the amount of the asset from the available to the locked balance of the user
+    pub fn lock_user_balance(
         &self,
-        order:&Order,
+        user_id:&str,
+        asset:&str,
+        amount:u64,
         user_balances:&Arc<Mutex<UserAssetBalance>>,
     ) -> Result<(), EngineError>{
 
-        let base_asset = &self.base_asset;
-        let quote_asset = QUOTE;        
+        let mut guard =  user_balances.lock().unwrap();
+
+        let user_balance = match guard.get_mut(user_id) {
+            Some(user_balance) => user_balance,
+            None => {
+                println!("user : {} not found", user_id);
+                return Err(EngineError::UserNotFound);
+            }
+        };
+
+        let asset_balance = user_balance.entry(asset.to_string()).or_default();
+
+        println!("{} {} balance before lock : {:?}", user_id, asset, asset_balance);
+        println!("amount to lock : {}", amount);
+
+        if asset_balance.available_amount < amount {
+            println!("user : {} doesnt have enough balance for asset : {:?} ", user_id, asset);
+            return Err(EngineError::InsufficientBalance);
+        }
+
+        asset_balance.locked_amount += amount;
+        asset_balance.available_amount -= amount;
 
-        let mut total_price;
-        let order_quantity = order.quantity;
+        println!("{} {} balance after lock : {:?}", user_id, asset, asset_balance);
+
+        Ok(())
+    }
 
-        let base_lamports = self.get_base_lamports();
+    /// moves the amount of the asset from the locked back to the available balance of the user
+    pub fn unlock_user_balance(
+        &self,
+        user_id:&str,
+        asset:&str,
+        amount:u64,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<(), EngineError>{
 
         let mut guard =  user_balances.lock().unwrap();
-        let user_asset_balance = guard.get_mut(&order.user_id);
 
-        let asset;
+        let asset_balance = guard.get_mut(user_id)
+        .and_then(|user_balance| user_balance.get_mut(asset))
+        .ok_or_else(||{
+            println!("{} balance not found for user : {}", asset, user_id);
+            EngineError::InternalError
+        })?;
 
-        match user_asset_balance {
+        asset_balance.locked_amount -= amount;
+        asset_balance.available_amount += amount;
 
-            Some(user_balance) => {
+        println!("{} {} balance after unlock : {:?}", user_id, asset, asset_balance);
 
-                let asset_balance = match order.side {
+        Ok(())
+    }
 
-                    OrderSide::Buy => {
+    /// locks the balance needed for the limit order and returns the locked amount
+    pub fn validate_and_lock_user_balance(
+        &self,
+        order:&Order,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<u64, EngineError>{
 
-                        asset = quote_asset;
-                        
-                        // consider this
-                        // total_price = 150.02  * 2.5 * 1000_000
-                        // total_price = 375050000.000
-                        total_price = order.price * order_quantity * Decimal::from(QUOTE_LAMPORTS);
+        // the amount can become a decimal even after multiplying with lamports,
+        // so the extra decimals are cut ex: 150234567.43435345 => 150234567
+        let (asset, total_amount) = match order.side {
+            OrderSide::Buy => {
+                // consider this
+                // total_price = 150.02  * 2.5 * 1000_000
+                // total_price = 375050000.000
+                let notional = order.price.checked_mul(order.quantity);
+                (&self.quote_asset, notional.and_then(|notional| OrderBook::to_lamports(notional, self.get_quote_lamports())))
+            },
+            OrderSide::Sell => {
+                // consider this
+                // total_price = 150.02  * 1000_000
+                // total_price = 150020000.000
+                (&self.base_asset, OrderBook::to_lamports(order.quantity, self.get_base_lamports()))
+            }
+        };
 
-                        // the total_price can become a decimal even after multiplying with lamports
-                        // so use trunc() to cut the extra decimals
-                        // ex: 150234567.43435345 => 150234567
+        let total_amount = total_amount.ok_or_else(||{
+            println!("amount to lock for order : {} doesn't fit in lamports", order.id);
+            EngineError::AboveMaxNotional
+        })?;
 
-                        total_price = total_price.trunc();
+        self.lock_user_balance(&order.user_id, asset, total_amount, user_balances)?;
 
-                        if user_balance.get(quote_asset).is_none(){
-                            user_balance.insert(quote_asset.to_string(), AssetBalance::new());
-                        }
+        Ok(total_amount)
+    }
 
-                        user_balance.get_mut(quote_asset).unwrap()
+    /// walks the opposing side like the matching would, till the limit price of the order,
+    /// and returns the quantity the order can fill along with its value in quote asset
+    pub fn get_order_sweep(&self, order:&Order) -> (Quantity, Decimal) {
 
-                    },
-                    OrderSide::Sell => {
+        let levels: Box<dyn Iterator<Item = (&Price, &OrdersWithQuantity)>> = match order.side {
+            OrderSide::Buy => Box::new(self.asks.iter()),
+            OrderSide::Sell => Box::new(self.bids.iter().rev()),
+        };
 
-                        asset = base_asset;
+        let mut quantity = dec!(0);
+        let mut quote_amount = dec!(0);
 
-                        // consider this
-                        // total_price = 150.02  * 1000_000
-                        // total_price = 150020000.000
+        for (price, orders_with_quantity) in levels {
 
-                        total_price = order_quantity * Decimal::from(base_lamports);
+            let within_limit = match (order.get_execution_type(), order.side) {
+                (OrderType::Market, _) => true,
+                (_, OrderSide::Buy) => price <= &order.price,
+                (_, OrderSide::Sell) => price >= &order.price,
+            };
 
-                        // the total_price can become a decimal even after multiplying with lamports
-                        // so use trunc() to cut the extra decimals
-                        // ex: 15023456723.43435345 => 15023456723
-                        total_price = total_price.trunc();
+            if !within_limit {
+                break;
+            }
 
-                        if user_balance.get(base_asset).is_none() {
-                            user_balance.insert(base_asset.to_string(), AssetBalance::new());
-                        }
+            let level_quantity = match order.quote_quantity {
+                Some(quote_quantity) => {
+                    // only whole lots can be filled with the remaining quote
+                    let lots = ((quote_quantity - quote_amount) / (price * self.lot_size)).floor();
+                    (lots * self.lot_size).min(orders_with_quantity.total_quantity)
+                },
+                None => (order.quantity - quantity).min(orders_with_quantity.total_quantity),
+            };
 
-                        user_balance.get_mut(base_asset).unwrap()
-                    },
-                };
+            if level_quantity <= dec!(0) {
+                break;
+            }
 
-                let total_amount = total_price.to_u64().expect("None while converting total_price in locking user balance");
+            quantity += level_quantity;
+            quote_amount += level_quantity * price;
+        }
 
-                println!("{} {} balance before lock : {:?}", &order.user_id, &asset, asset_balance);
-                println!("amount to lock : {}", total_amount);
+        (quantity, quote_amount)
+    }
 
-                if asset_balance.available_amount >= total_amount {
-                    asset_balance.locked_amount += total_amount;
-                    asset_balance.available_amount -= total_amount;
+    /// asset locked by the orders on the side
+    pub fn get_locked_asset(&self, side:OrderSide) -> &str {
+        match side {
+            OrderSide::Buy => &self.quote_asset,
+            OrderSide::Sell => &self.base_asset,
+        }
+    }
 
-                    println!("{} {} balance after lock : {:?}", &order.user_id, &asset, asset_balance);
-                }
-                else{
-                    // DROPPING IS NECESSARY, ELSE THIS WONT UNLOCK THE MUTEX
-                    drop(guard);
-                    println!("user : {} doesnt have enough balance for asset : {:?} ", &order.user_id, &asset);
-                    return Err(EngineError::InsufficientBalance);
-                }
+    /// sizes the market order from the book and returns the amount of the asset to lock for it.
+    /// buys lock the quote amount as the final price is only known after the sweep,
+    /// the unused part is released by `release_unused_order_balance`
+    pub fn size_market_order(&self, order:&mut Order) -> Result<u64, EngineError>{
 
+        let (quantity, quote_amount) = self.get_order_sweep(order);
+
+        if quantity == dec!(0) {
+            println!("cant place market order : {} as there are no orders to match on the book", order.id);
+            return Err(EngineError::InsufficientLiquidity);
+        }
+
+        if quote_amount < self.min_notional {
+            println!("notional : {} of market order : {} is less than min notional : {}", quote_amount, order.id, self.min_notional);
+            return Err(EngineError::BelowMinNotional);
+        }
+
+        // quantity of the orders sized by quote is only known after the sweep
+        if order.quote_quantity.is_some() {
+            order.quantity = quantity;
+            self.validate_quantity_limits(order)?;
+        }
+
+        let amount = match order.side {
+            OrderSide::Buy => {
+                let quote_to_lock = order.quote_quantity.unwrap_or(quote_amount);
+                OrderBook::to_lamports(quote_to_lock, self.get_quote_lamports())
             },
-            None => {
-                // DROPPING IS NECESSARY, ELSE THIS WONT UNLOCK THE MUTEX
-                drop(guard);
-                println!("user : {} not found", order.user_id);
-                return Err(EngineError::UserNotFound);
-            },
+            OrderSide::Sell => {
+                OrderBook::to_lamports(order.quantity, self.get_base_lamports())
+            }
         };
 
-        return Ok(());
-
+        amount.ok_or_else(||{
+            println!("amount to lock for market order : {} doesn't fit in lamports", order.id);
+            EngineError::AboveMaxNotional
+        })
     }
 
-    pub fn get_desc_bids(&mut self) -> Vec<(&Decimal, &mut OrdersWithQuantity)>{
-        let mut bids:Vec<(&Decimal, &mut OrdersWithQuantity)> = self.bids.iter_mut().collect();
-        bids.sort_by(|a, b| b.0.cmp(a.0));
-        bids
+    /// sizes the market order from the book and locks the balance needed to fill it
+    pub fn lock_market_order_balance(
+        &self,
+        order:&mut Order,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<u64, EngineError>{
+
+        let amount = self.size_market_order(order)?;
+
+        self.lock_user_balance(&order.user_id, self.get_locked_asset(order.side), amount, user_balances)?;
+
+        Ok(amount)
     }
 
-    pub fn get_asc_asks(&mut self) -> Vec<(&Decimal, &mut OrdersWithQuantity)>{
+    /// releases whatever is left of the locked balance of the order after settling the fills,
+    /// except what stays locked for its resting quantity. the unfilled part of the orders
+    /// which don't rest and the price improvement of the buys filled below their price are released
+    pub fn release_unused_order_balance(
+        &self,
+        order:&Order,
+        locked_amount:u64,
+        filled_orders:&[Fill],
+        resting_quantity:Quantity,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<(), EngineError>{
+
+        let (asset, used_amount) = match order.side {
+            OrderSide::Buy => {
+                let spent: Decimal = filled_orders.iter().map(|fill| fill.filled_quantity * fill.price).sum();
+                (&self.quote_asset, (spent + resting_quantity * order.price) * Decimal::from(self.get_quote_lamports()))
+            },
+            OrderSide::Sell => {
+                (&self.base_asset, (order.filled + resting_quantity) * Decimal::from(self.get_base_lamports()))
+            }
+        };
+
+        let used_amount = used_amount.to_u64().ok_or_else(||{
+            println!("none while converting used amount : {} of order : {}", used_amount, order.id);
+            EngineError::InternalError
+        })?;
+
+        let unused_amount = locked_amount.saturating_sub(used_amount);
+
+        if unused_amount == 0 {
+            return Ok(());
+        }
 
-        let mut asks:Vec<(&Decimal, &mut OrdersWithQuantity)> = self.asks.iter_mut().collect();
-        asks.sort_by(|a, b| a.0.cmp(b.0));
-        asks
+        println!("releasing unused {} {} locked for order : {}", unused_amount, asset, order.id);
+
+        self.unlock_user_balance(&order.user_id, asset, unused_amount, user_balances)
+    }
+
+    /// levels of the side from the best price, bids are descending and asks are ascending
+    pub fn get_levels_from_best(&mut self, side:OrderSide) -> Box<dyn Iterator<Item = (&Price, &mut OrdersWithQuantity)> + '_>{
+        match side {
+            OrderSide::Buy => Box::new(self.bids.iter_mut().rev()),
+            OrderSide::Sell => Box::new(self.asks.iter_mut()),
+        }
     }
 
     /// When the orders are completely filled, remove it from the orderbook
@@ -230,9 +460,10 @@ impl OrderBook {
 
                     // retain all orders except the complete fill order
                     orders.retain(|o| o.id != complete_fill_order_id);
+                    self.order_index.remove(&complete_fill_order_id);
 
                     // remove the price entry when there are no orders
-                    if orders.len() == 0 {
+                    if orders.is_empty() {
                         println!("removing price entry : {} from {:?}", &complete_fill_order.price, filled_side);
                         price_w_orders_n_quantity.remove(&complete_fill_order.price);
                     }
@@ -260,25 +491,27 @@ impl OrderBook {
         let mut trade_id = self.trade_id;
         let mut last_price = self.last_price;
 
-        let opposing_side_with_orders = match order.side{
-
-            OrderSide::Buy => {
-                let asks = self.get_asc_asks();
-                asks
-            },
-            OrderSide::Sell => {
-                let bids = self.get_desc_bids();
-                bids
-            }
+        // each side pays the fee in the asset it receives
+        let (taker_fee_asset, maker_fee_asset) = match order.side {
+            OrderSide::Buy => (self.base_asset.clone(), self.quote_asset.clone()),
+            OrderSide::Sell => (self.quote_asset.clone(), self.base_asset.clone()),
         };
 
+        let opposing_side_with_orders = self.get_levels_from_best(order.get_opposing_side());
+
         for (opposing_price, orders_with_quantity) in opposing_side_with_orders {
 
-            let can_match_orders = match order.side {
-                OrderSide::Buy => {
+            if remaining_quantity == dec!(0) {
+                break;
+            }
+
+            // market orders are not limited by a price, they sweep the levels till filled
+            let can_match_orders = match (order.get_execution_type(), order.side) {
+                (OrderType::Market, _) => true,
+                (_, OrderSide::Buy) => {
                     opposing_price <= &order.price
                 },
-                OrderSide::Sell => {
+                (_, OrderSide::Sell) => {
                     opposing_price >= &order.price
                 }
             };
@@ -319,7 +552,12 @@ impl OrderBook {
                     trade_id,
                     price: *opposing_price,
                     quantity: opposing_order.quantity,
-                    filled_quantity: filled_quantity,
+                    filled_quantity,
+                    order_filled_quantity: opposing_order.filled,
+                    taker_fee: dec!(0),
+                    taker_fee_asset: taker_fee_asset.clone(),
+                    maker_fee: dec!(0),
+                    maker_fee_asset: maker_fee_asset.clone(),
                 };   
 
                 println!("matched fill : {:?} for order id: {}", fill, order.id);
@@ -374,6 +612,8 @@ impl OrderBook {
             }
         };
 
+        self.order_index.insert(order.id.clone(), OrderLocation { side: order_side, price });
+
         let orders_w_qty_res = price_w_orders_n_qty.get_mut(&price);
 
         match orders_w_qty_res {
@@ -399,11 +639,30 @@ impl OrderBook {
 
     }   
 
+    /// lamports of the asset traded on the market
+    pub fn get_asset_lamports(&self, asset:&str) -> u64 {
+        match asset == self.base_asset {
+            true => self.get_base_lamports(),
+            false => self.get_quote_lamports(),
+        }
+    }
+
+    /// fee in lamports charged on the received amount, negative for a rebate
+    fn get_fee_in_lamports(received_amount:u64, rate:Decimal) -> i128 {
+        (Decimal::from(received_amount) * rate)
+        .trunc()
+        .to_i128()
+        .expect("None while converting fee in settling user balance")
+    }
+
+    /// moves the traded amounts between the makers and the taker.
+    /// both pay the fee in the asset they receive, the fees are credited to the fee account
+    /// and the maker rebates are paid from it, but never more than it has collected.
     pub fn settle_user_balance(
         &self,
         user_id:String,
         order_side:OrderSide,
-        filled_orders: &Vec<Fill>,
+        filled_orders: &mut [Fill],
         user_balances:Arc<Mutex<UserAssetBalance>>,
     ){
 
@@ -411,19 +670,21 @@ impl OrderBook {
 
         let mut user_base_amount = 0_u64;
         let mut user_quote_amount = 0_u64;
+        let mut user_fee_amount = 0_u64;
+
+        let fee_account = &self.fee_schedule.fee_account;
+        let taker_rate = self.fee_schedule.get_tier(&user_id).taker_rate;
 
         // settle makers
-        for filled_order in filled_orders.iter() {
+        for filled_order in filled_orders.iter_mut() {
 
-            let maker_asset_balance = guard.get_mut(&filled_order.maker_id).unwrap();
-            
             let quantity = filled_order.filled_quantity;
             let price = filled_order.price;
 
             let base_lamports = self.get_base_lamports();
 
             let base_amount_in_lamports = quantity * Decimal::from(base_lamports);
-            let quote_amount_in_lamports = quantity * price * Decimal::from(QUOTE_LAMPORTS);  
+            let quote_amount_in_lamports = quantity * price * Decimal::from(self.get_quote_lamports());  
 
             // convert to u64 
             let base_amount_in_lamports = base_amount_in_lamports
@@ -437,6 +698,48 @@ impl OrderBook {
             user_base_amount += base_amount_in_lamports;
             user_quote_amount += quote_amount_in_lamports;
 
+            let (taker_received, maker_received) = match order_side {
+                OrderSide::Buy => (base_amount_in_lamports, quote_amount_in_lamports),
+                OrderSide::Sell => (quote_amount_in_lamports, base_amount_in_lamports),
+            };
+
+            let maker_rate = self.fee_schedule.get_tier(&filled_order.maker_id).maker_rate;
+
+            // taker rate is never negative
+            let taker_fee = OrderBook::get_fee_in_lamports(taker_received, taker_rate) as u64;
+            let mut maker_fee = OrderBook::get_fee_in_lamports(maker_received, maker_rate);
+
+            user_fee_amount += taker_fee;
+
+            let fee_account_balance = guard.entry(fee_account.clone()).or_default();
+
+            let taker_fee_balance = fee_account_balance.entry(filled_order.taker_fee_asset.clone()).or_default();
+            taker_fee_balance.available_amount += taker_fee;
+
+            let maker_fee_balance = fee_account_balance.entry(filled_order.maker_fee_asset.clone()).or_default();
+
+            if maker_fee >= 0 {
+                maker_fee_balance.available_amount += maker_fee as u64;
+            }
+            else {
+                let rebate = (-maker_fee).min(i128::from(maker_fee_balance.available_amount));
+
+                if rebate < -maker_fee {
+                    println!("fee account has only {} {} to pay the rebate of {}", maker_fee_balance.available_amount, filled_order.maker_fee_asset, -maker_fee);
+                }
+
+                maker_fee_balance.available_amount -= rebate as u64;
+                maker_fee = -rebate;
+            }
+
+            let maker_credit = u64::try_from(i128::from(maker_received) - maker_fee)
+            .expect("None while converting maker credit in settling user balance");
+
+            filled_order.taker_fee = (Decimal::from(taker_fee) / Decimal::from(self.get_asset_lamports(&filled_order.taker_fee_asset))).normalize();
+            filled_order.maker_fee = (Decimal::from(maker_fee) / Decimal::from(self.get_asset_lamports(&filled_order.maker_fee_asset))).normalize();
+
+            let maker_asset_balance = guard.get_mut(&filled_order.maker_id).unwrap();
+
             match order_side {
                 OrderSide::Buy => {
                     // Increment the Quote and Decrement the Base
@@ -444,17 +747,19 @@ impl OrderBook {
                     let maker_base_balance = maker_asset_balance.get_mut(&self.base_asset).unwrap();
                     maker_base_balance.locked_amount -= base_amount_in_lamports;
 
-                    let maker_quote_balance = maker_asset_balance.get_mut(QUOTE).unwrap();
-                    maker_quote_balance.available_amount += quote_amount_in_lamports;
+                    // maker may not hold the quote asset yet
+                    let maker_quote_balance = maker_asset_balance.entry(self.quote_asset.clone()).or_default();
+                    maker_quote_balance.available_amount += maker_credit;
                     
                 },
                 OrderSide::Sell => {
                     // Increment the Base and decrement the Quote
 
-                    let maker_base_balance = maker_asset_balance.get_mut(&self.base_asset).unwrap();
-                    maker_base_balance.available_amount += base_amount_in_lamports;
+                    // maker may not hold the base asset yet
+                    let maker_base_balance = maker_asset_balance.entry(self.base_asset.clone()).or_default();
+                    maker_base_balance.available_amount += maker_credit;
 
-                    let maker_quote_balance = maker_asset_balance.get_mut(QUOTE).unwrap();
+                    let maker_quote_balance = maker_asset_balance.get_mut(&self.quote_asset).unwrap();
                     maker_quote_balance.locked_amount -= quote_amount_in_lamports;
                 }
             }
@@ -468,94 +773,179 @@ impl OrderBook {
             OrderSide::Buy => {
                 // INCREMENT BASE AND DECREMENT QUOTE
 
-                println!("total base amount to add : {} and quote amount to reduce : {}", user_base_amount, user_quote_amount);
+                println!("total base amount to add : {} with fee : {} and quote amount to reduce : {}", user_base_amount, user_fee_amount, user_quote_amount);
                 
-                let user_base_balance = user_asset_balance.get_mut(&self.base_asset).unwrap();
+                let user_base_balance = user_asset_balance.entry(self.base_asset.clone()).or_default();
                 println!("{} {} balance before settling : {:?}", &user_id, &self.base_asset, user_base_balance);
-                user_base_balance.available_amount += user_base_amount;
+                user_base_balance.available_amount += user_base_amount - user_fee_amount;
                 println!("{} {} balance after settling : {:?}", &user_id, &self.base_asset, user_base_balance);
 
-                let user_quote_balance = user_asset_balance.get_mut(QUOTE).unwrap();
-                println!("{} {} balance before settling : {:?}", &user_id, QUOTE, user_quote_balance);
+                let user_quote_balance = user_asset_balance.get_mut(&self.quote_asset).unwrap();
+                println!("{} {} balance before settling : {:?}", &user_id, &self.quote_asset, user_quote_balance);
                 user_quote_balance.locked_amount -= user_quote_amount;
-                println!("{} {} balance after settling : {:?}", &user_id, QUOTE, user_quote_balance);
+                println!("{} {} balance after settling : {:?}", &user_id, &self.quote_asset, user_quote_balance);
 
             },
             OrderSide::Sell => {
                 // DECREMENT BASE AND INCREMENT QUOTE
 
-                println!("total base amount to reduce : {} and quote amount to add : {}", user_base_amount, user_quote_amount);
+                println!("total base amount to reduce : {} and quote amount to add : {} with fee : {}", user_base_amount, user_quote_amount, user_fee_amount);
 
                 let user_base_balance = user_asset_balance.get_mut(&self.base_asset).unwrap();
                 println!("{} {} balance before settling : {:?}", &user_id, &self.base_asset, user_base_balance);
                 user_base_balance.locked_amount -= user_base_amount;
                 println!("{} {} balance after settling : {:?}", &user_id, &self.base_asset, user_base_balance);
 
-                let user_quote_balance = user_asset_balance.get_mut(QUOTE).unwrap();
-                println!("{} {} balance before settling : {:?}", &user_id, QUOTE, user_quote_balance);
-                user_quote_balance.available_amount += user_quote_amount;
-                println!("{} {} balance after settling : {:?}", &user_id, QUOTE, user_quote_balance);
+                let user_quote_balance = user_asset_balance.entry(self.quote_asset.clone()).or_default();
+                println!("{} {} balance before settling : {:?}", &user_id, &self.quote_asset, user_quote_balance);
+                user_quote_balance.available_amount += user_quote_amount - user_fee_amount;
+                println!("{} {} balance after settling : {:?}", &user_id, &self.quote_asset, user_quote_balance);
             }
         }
 
 
     }
 
+    /// true if the order would match any order on the opposing side
+    pub fn crosses_book(&self, order:&Order) -> bool {
+        match order.side {
+            OrderSide::Buy => self.asks.first_key_value().is_some_and(|(price, _)| price <= &order.price),
+            OrderSide::Sell => self.bids.last_key_value().is_some_and(|(price, _)| price >= &order.price),
+        }
+    }
 
-    pub fn can_place_market_order(&self, order:&Order) -> Result<(), EngineError> {
+    /// rejects the order if its quantity is outside the min and max quantity of the market
+    pub fn validate_quantity_limits(&self, order:&Order) -> Result<(), EngineError> {
 
-        let price = order.price;
-        let quantities_to_match = order.quantity;
+        if order.quantity < self.min_quantity {
+            println!("quantity : {} of order : {} is less than min quantity : {}", order.quantity, order.id, self.min_quantity);
+            return Err(EngineError::BelowMinQuantity);
+        }
 
-        let price_w_orders_n_qty = match order.side {
-            OrderSide::Buy => {
-                &self.asks
-            },
-            OrderSide::Sell => {
-                &self.bids
-            }
+        if order.quantity > self.max_quantity {
+            println!("quantity : {} of order : {} is more than max quantity : {}", order.quantity, order.id, self.max_quantity);
+            return Err(EngineError::AboveMaxQuantity);
+        }
+
+        Ok(())
+    }
+
+    /// rejects the order if it doesn't follow the rules of the market,
+    /// the price and quantity of the order are never altered
+    pub fn validate_order(&self, order:&Order) -> Result<(), EngineError> {
+
+        // trigger orders follow the rules of the order they are placed as
+        let execution_type = order.get_execution_type();
+
+        // market orders never sit on the book and FOK needs a quantity to check against
+        let is_valid_time_in_force = match (execution_type, order.time_in_force) {
+            (OrderType::Market, TimeInForce::PostOnly) => false,
+            (OrderType::Market, TimeInForce::Fok) => order.quote_quantity.is_none(),
+            _ => true,
         };
 
-        match price_w_orders_n_qty.get(&price) {
-            None => {
-                // throw error order cannot be matched
-                println!("
-                    cant place market order : {} as 0 orders available on price : {}",
-                    order.id,
-                    order.price,
-                );
-
-                Err(EngineError::PartialOrderFill)
-                
-            },
-            Some(orders_w_qty) => {
+        if !is_valid_time_in_force {
+            println!("time in force : {:?} is not supported for order : {}", order.time_in_force, order.id);
+            return Err(EngineError::InvalidTimeInForce);
+        }
 
-                let available_quantities = orders_w_qty.total_quantity;
+        // price of market orders is ignored, they fill at the best available prices
+        if execution_type == OrderType::Limit {
 
-                if available_quantities >= quantities_to_match {
-                    Ok(())
-                }
-                else{
-                    println!(
-                        "cant place market order :{} as expected: {} available: {} on price : {}",
-                        order.id,
-                        quantities_to_match, 
-                        available_quantities,
-                        order.price,
-                    );
+            if order.price <= dec!(0) {
+                return Err(EngineError::InvalidPrice);
+            }
+
+            if order.price % self.tick_size != dec!(0) {
+                println!("price : {} of order : {} is not a multiple of tick size : {}", order.price, order.id, self.tick_size);
+                return Err(EngineError::InvalidTickSize);
+            }
+        }
+
+        if order.is_trigger_order() {
+            self.validate_trigger_order(order)?;
+        }
+
+        if let Some(quote_quantity) = order.quote_quantity {
 
-                    Err(EngineError::PartialOrderFill)
+            let is_valid = execution_type == OrderType::Market
+            && order.quantity == dec!(0)
+            && quote_quantity > dec!(0)
+            && quote_quantity.normalize().scale() <= u32::from(self.quote_decimals);
+
+            if !is_valid {
+                println!("invalid quote quantity : {} of order : {}", quote_quantity, order.id);
+                return Err(EngineError::InvalidQuoteQuantity);
+            }
+
+            if quote_quantity < self.min_notional {
+                return Err(EngineError::BelowMinNotional);
+            }
+
+            if OrderBook::to_lamports(quote_quantity, self.get_quote_lamports()).is_none() {
+                return Err(EngineError::AboveMaxNotional);
+            }
+
+            return Ok(());
+        }
+
+        if order.quantity <= dec!(0) {
+            return Err(EngineError::InvalidQuantity);
+        }
+
+        if order.quantity % self.lot_size != dec!(0) {
+            println!("quantity : {} of order : {} is not a multiple of lot size : {}", order.quantity, order.id, self.lot_size);
+            return Err(EngineError::InvalidLotSize);
+        }
+
+        self.validate_quantity_limits(order)?;
+
+        // notional of market orders is checked against the book when they are locked
+        if execution_type == OrderType::Limit {
+
+            // the notional is settled in lamports of the quote asset, on both sides
+            let notional = match order.price.checked_mul(order.quantity) {
+                Some(notional) if OrderBook::to_lamports(notional, self.get_quote_lamports()).is_some() => notional,
+                _ => {
+                    println!("notional of order : {} with price : {} doesn't fit in lamports", order.id, order.price);
+                    return Err(EngineError::AboveMaxNotional);
                 }
+            };
 
+            if notional < self.min_notional {
+                println!("notional : {} of order : {} is less than min notional : {}", notional, order.id, self.min_notional);
+                return Err(EngineError::BelowMinNotional);
             }
         }
+
+        Ok(())
+    }
+
+    /// rejects the order if its time in force can't be met on the current book
+    pub fn check_time_in_force(&self, order:&Order) -> Result<(), EngineError> {
+
+        if order.time_in_force == TimeInForce::PostOnly && self.crosses_book(order) {
+            println!("cant place post only order : {} as it would match on price : {}", order.id, order.price);
+            return Err(EngineError::PostOnlyWouldCross);
+        }
+
+        if order.time_in_force == TimeInForce::Fok {
+            let (fillable_quantity, _) = self.get_order_sweep(order);
+
+            if fillable_quantity < order.quantity {
+                println!("cant place FOK order : {} as only {} of {} can be filled", order.id, fillable_quantity, order.quantity);
+                return Err(EngineError::PartialOrderFill);
+            }
+        }
+
+        Ok(())
     }
 
     pub fn process_order(
         &mut self, 
         order:&mut Order,
         user_balances:Arc<Mutex<UserAssetBalance>>,
-    ) -> Result<(OrderPlacedResponse, PriceWithDepth), EngineError>{
+    ) -> Result<(ExecutedOrder, PriceWithDepth, Vec<TriggeredOrder>), EngineError>{
 
         /*
             - Check user has enough balance
@@ -563,56 +953,104 @@ impl OrderBook {
             - Get the opposiing orders,
             - match the opposing orders till the opposing order price becomes greater than order price
             - after matching settle the user balances
+            - place the trigger orders reached by the new last price
         */
 
-        // Truncate price and quantity 
-        order.price = order.price.trunc_with_scale(9);
-        order.quantity = order.quantity.trunc_with_scale(6);
+        if !self.enabled {
+            println!("cant place order : {} as market : {} is disabled", order.id, self.market);
+            return Err(EngineError::MarketDisabled);
+        }
 
-        let maker_side = order.get_opposing_side();
+        self.validate_order(order)?;
+
+        if order.is_trigger_order() {
+            let order_placed = self.add_trigger_order(order, &user_balances)?;
+            return Ok((ExecutedOrder { order_placed, fills: vec![] }, PriceWithDepth::new(), vec![]));
+        }
+
+        self.check_time_in_force(order)?;
 
+        let locked_amount = match order.order_type {
+            OrderType::Market => self.lock_market_order_balance(order, &user_balances)?,
+            _ => self.validate_and_lock_user_balance(order, &user_balances)?,
+        };
+
+        let last_price = self.last_price;
         let mut price_w_depth = PriceWithDepth::new();
 
-        if order.order_type == OrderType::Market {
-            self.can_place_market_order(order)?;
-        }
+        let executed = self.execute_order(order, locked_amount, &mut price_w_depth, &user_balances)?;
+
+        let triggered_orders = match self.last_price != last_price {
+            true => self.execute_trigger_orders(&mut price_w_depth, &user_balances),
+            false => vec![],
+        };
 
-        self.validate_and_lock_user_balance(order, &user_balances)?;
+        Ok((executed, price_w_depth, triggered_orders))
+    }
+
+    /// matches the order whose balance is already locked, then
+    /// rests or cancels the unfilled part and settles the balances
+    pub fn execute_order(
+        &mut self,
+        order:&mut Order,
+        locked_amount:u64,
+        price_w_depth:&mut PriceWithDepth,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<ExecutedOrder, EngineError>{
+
+        let maker_side = order.get_opposing_side();
 
         let (
-            filled_orders,
+            mut filled_orders,
             complete_fill_orders
-        ) = self.match_opposing_orders(order, &mut price_w_depth);
+        ) = self.match_opposing_orders(order, price_w_depth);
         
         self.remove_complete_filled_orders(complete_fill_orders, maker_side);
 
-        // sit on the orderbook !!
+        let mut cancelled_quantity = dec!(0);
+        let mut resting_quantity = dec!(0);
+
         if order.filled < order.quantity {
-            self.add_order(order.clone(), &mut price_w_depth);
-        }   
+            match (order.order_type, order.time_in_force) {
+                // sit on the orderbook !!
+                (OrderType::Limit, TimeInForce::Gtc | TimeInForce::PostOnly) => {
+                    resting_quantity = order.quantity - order.filled;
+                    self.add_order(order.clone(), price_w_depth);
+                },
+                _ => {
+                    cancelled_quantity = order.quantity - order.filled;
+                    println!("cancelling unfilled quantity : {} of order : {}", cancelled_quantity, order.id);
+                }
+            }
+        }
 
         self.settle_user_balance(
             order.user_id.clone(), 
             order.side, 
-            &filled_orders, 
-            user_balances
+            &mut filled_orders, 
+            user_balances.clone()
         );
 
-        let filled_orders:Vec<OrderFill> = filled_orders.iter().map(|o| OrderFill{
+        self.release_unused_order_balance(order, locked_amount, &filled_orders, resting_quantity, user_balances)?;
+
+        let order_fills:Vec<OrderFill> = filled_orders.iter().map(|o| OrderFill{
             order_id: o.order_id.clone(),
             price: o.price,
             quantity: o.quantity,
             filled_quantity: o.filled_quantity,
             trade_id: o.trade_id,
+            fee: o.taker_fee,
+            fee_asset: o.taker_fee_asset.clone(),
         }).collect();
 
         let order_placed = OrderPlacedResponse {
             executed_quantity: order.filled,
+            cancelled_quantity,
             order_id: order.id.clone(),
-            fills: filled_orders,
+            fills: order_fills,
         };
 
-        Ok((order_placed, price_w_depth))
+        Ok(ExecutedOrder { order_placed, fills: filled_orders })
     }
 
     pub fn settle_balance_after_cancel(
@@ -640,9 +1078,9 @@ impl OrderBook {
 
                 match cancelled_order.side {
                     OrderSide::Buy => {
-                        asset = QUOTE;
+                        asset = &self.quote_asset;
                         println!("remaining : {remaining_qty} , cancelled_price:{cancelled_price}");
-                        total_amount = (remaining_qty * cancelled_price * Decimal::from(QUOTE_LAMPORTS)).to_u64();
+                        total_amount = (remaining_qty * cancelled_price * Decimal::from(self.get_quote_lamports())).to_u64();
                     },
                     OrderSide::Sell => {
                         asset = &self.base_asset;
@@ -676,98 +1114,79 @@ impl OrderBook {
 
     }
 
-    /// returns cancelled_order with price
-    pub fn cancel_order_in_side(
+    /// removes the resting order from its level and returns it with its price,
+    /// None if the order is not resting on the book
+    pub fn cancel_order_on_book(
         &mut self, 
-        side:OrderSide,
         target_order_id: &String,
         user_id:&String,
         price_w_updated_depths: &mut PriceWithDepth
     ) -> Result<Option<(OrderCancelledResponse, Decimal)>, EngineError>{    
 
+        let OrderLocation { side, price } = match self.order_index.get(target_order_id) {
+            Some(location) => *location,
+            None => return Ok(None),
+        };
+
         let price_w_orders_n_qty = match side{
             OrderSide::Buy => &mut self.bids,
             OrderSide::Sell => &mut self.asks,
         };
 
-        let mut final_res = None;
-
-        for (price, orders_w_qty) in price_w_orders_n_qty.iter_mut(){
-
-            let mut target_order_index = -1;
-
-            //iterate through orders of the price range
-            for (index, order) in orders_w_qty.orders.iter().enumerate() {
-                if order.id == *target_order_id {
-                    target_order_index = index as i32;
-                    break;
-                }
+        let orders_w_qty = match price_w_orders_n_qty.get_mut(&price) {
+            Some(orders_w_qty) => orders_w_qty,
+            None => {
+                println!("price : {} of order : {} not found on {:?}", price, target_order_id, side);
+                return Err(EngineError::InternalError);
             }
+        };
 
-            // if found cancel it!!
-            if target_order_index != -1 {
-
-                let target_order_index = target_order_index as usize;
-
-                match orders_w_qty.orders.get(target_order_index) {
-
-                    Some(target_order) => {
+        let target_order_index = match orders_w_qty.orders.iter().position(|order| order.id == *target_order_id) {
+            Some(index) => index,
+            None => {
+                println!("Cannnot find target order : {} in orders of price : {}", target_order_id, price);
+                return Err(EngineError::InternalError);
+            }
+        };
 
-                        if target_order.user_id != *user_id {
-                            println!("{} cannot cancel the order : {:?}", user_id, target_order);
-                            return Err(EngineError::MismatchUser);                            
-                        }
+        let target_order = &orders_w_qty.orders[target_order_index];
 
-                        let order = orders_w_qty.orders.remove(target_order_index);
-                        let unfilled_qty = order.quantity - order.filled;
+        if target_order.user_id != *user_id {
+            println!("{} cannot cancel the order : {:?}", user_id, target_order);
+            return Err(EngineError::MismatchUser);                            
+        }
 
-                        println!("No of Quantities: {} in price : {} on  {:?} before updating", orders_w_qty.total_quantity, price, side);
+        let order = orders_w_qty.orders.remove(target_order_index);
+        let unfilled_qty = order.quantity - order.filled;
 
-                        orders_w_qty.total_quantity -= unfilled_qty;
+        println!("No of Quantities: {} in price : {} on  {:?} before updating", orders_w_qty.total_quantity, price, side);
 
-                        let total_quantity = orders_w_qty.total_quantity;
+        orders_w_qty.total_quantity -= unfilled_qty;
 
-                        price_w_updated_depths.update_depth(side, *price, total_quantity);
+        let total_quantity = orders_w_qty.total_quantity;
 
-                        println!("No of Quantities: {} in price : {} on  {:?} after updating", orders_w_qty.total_quantity, price, side);
+        price_w_updated_depths.update_depth(side, price, total_quantity);
 
-                        let order_cancelled = OrderCancelledResponse {
-                            order_id: order.id,
-                            quantity: order.quantity,
-                            executed_quantity: order.filled,
-                            side
-                        };
+        println!("No of Quantities: {} in price : {} on  {:?} after updating", orders_w_qty.total_quantity, price, side);
 
-                        println!("cancelled order : {:?}", order_cancelled);
-                        final_res = Some((order_cancelled, *price));
-                        break;
-                    },
-                    None =>{
-                        println!("Cannnot find target order to cancel in orders at index : {}", target_order_index);
-                        return Err(EngineError::InternalError);
-                    }
-                }
-            }
+        // if there are no orders available the clearup the price and orders
+        if orders_w_qty.orders.is_empty() {
+            price_w_orders_n_qty.remove(&price);
         }
 
-        match final_res {
-            Some(res) => {
-
-                let price = res.1;
+        self.order_index.remove(target_order_id);
 
-                // if there are no orders available the clearup the price and orders
-
-                if let Some(orders_w_qty) = price_w_orders_n_qty.get(&price) {
-                    if orders_w_qty.orders.len() == 0 {
-                        price_w_orders_n_qty.remove(&price);                        
-                    }
-                }
+        let order_cancelled = OrderCancelledResponse {
+            order_id: order.id,
+            quantity: order.quantity,
+            executed_quantity: order.filled,
+            side,
+            price,
+        };
 
-                Ok(Some(res))
-            },
-            None => Ok(None)
-        }
+        println!("cancelled order : {:?}", order_cancelled);
 
+        Ok(Some((order_cancelled, price)))
     }   
 
     pub fn cancel_order(
@@ -775,24 +1194,18 @@ impl OrderBook {
         order_payload:CancelOrderPayload,
         user_balances:Arc<Mutex<UserAssetBalance>>,
     ) -> Result<(OrderCancelledResponse, PriceWithDepth), EngineError>{
-        let mut order_cancelled_res;
-
         let mut price_w_updated_depth = PriceWithDepth::new();
 
-        order_cancelled_res = self.cancel_order_in_side(
-            OrderSide::Buy, 
+        let order_cancelled_res = self.cancel_order_on_book(
             &order_payload.order_id,
             &order_payload.user_id,
             &mut price_w_updated_depth
         )?;
 
         if order_cancelled_res.is_none() {
-            order_cancelled_res = self.cancel_order_in_side(
-                OrderSide::Sell, 
-                &order_payload.order_id,
-                &order_payload.user_id,
-                &mut price_w_updated_depth
-            )?;
+            if let Some(res) = self.cancel_trigger_order(&order_payload.order_id, &order_payload.user_id, &user_balances)? {
+                return Ok((res, price_w_updated_depth));
+            }
         }
 
         match order_cancelled_res {
@@ -831,8 +1244,8 @@ impl OrderBook {
 
         let mut price_with_empty_orders = vec![];
 
-        // iterate through all the price range and find the orders to remove
-        // using iter as to aboid value getting consumed
+        // levels are in ascending order of price and the orders
+        // of a level in time order, so are the cancelled orders
         for (price, orders_w_qty) in price_w_orders_n_qty.iter_mut() {
 
             let mut orders_to_remove = HashSet::new();
@@ -853,6 +1266,8 @@ impl OrderBook {
 
                     println!("order cancelled: {:?}", order);
 
+                    self.order_index.remove(&order.id);
+
                     cancelled_orders.push(
                         CancelAllOrders { 
                             order_id: order.id.clone(), 
@@ -865,21 +1280,21 @@ impl OrderBook {
 
                     return false;
                 }
-                return true;
+                true
             });
 
             let total_quantity = orders_w_qty.total_quantity;
 
             // update depth only if orders has removed on the price range
-            if orders_to_remove.len() > 0 {
+            if !orders_to_remove.is_empty() {
                 price_w_updated_depths.update_depth(side, *price, total_quantity);
             }
 
             // if there are no orders, then remove the price
             
-            if orders_w_qty.orders.len() == 0 {
+            if orders_w_qty.orders.is_empty() {
                 println!("no orders left in price : {} on : {:?}", price, side);
-                price_with_empty_orders.push(price.clone());
+                price_with_empty_orders.push(*price);
             }
 
         }
@@ -887,7 +1302,6 @@ impl OrderBook {
         for price in price_with_empty_orders {
             price_w_orders_n_qty.remove(&price);
         }
-        
 
     }
 
@@ -915,11 +1329,12 @@ impl OrderBook {
             &mut price_w_depths,
         );
 
+        // trigger orders unlock their balance on their own, so they are added after the book orders are settled
+        let cancelled_trigger_orders = self.cancel_all_trigger_orders(user_id, &user_balances)?;
+
         let mut guard = user_balances.lock().unwrap();
 
-        let user_asset_balance = guard.get_mut(user_id).ok_or_else(||{
-            EngineError::UserNotFound
-        })?;
+        let user_asset_balance = guard.get_mut(user_id).ok_or(EngineError::UserNotFound)?;
 
         for order in  cancelled_orders.iter() {
 
@@ -928,9 +1343,9 @@ impl OrderBook {
 
             match order.side {
                 OrderSide::Buy => {
-                    asset = QUOTE;
+                    asset = &self.quote_asset;
                     let remaining = order.quantity - order.executed_quantity;
-                    amount = order.price * remaining * Decimal::from(QUOTE_LAMPORTS);
+                    amount = order.price * remaining * Decimal::from(self.get_quote_lamports());
                 },
                 OrderSide::Sell => {
                     asset = &self.base_asset;
@@ -955,6 +1370,7 @@ impl OrderBook {
             asset_balance.available_amount += total_amount;
         }
 
+        cancelled_orders.extend(cancelled_trigger_orders);
 
         Ok((cancelled_orders, price_w_depths))
 
@@ -971,7 +1387,7 @@ impl OrderBook {
             OrderSide::Sell => &self.asks,
         };
 
-        for (_price, orders_w_qty) in price_w_orders_n_qty {
+        for orders_w_qty in price_w_orders_n_qty.values() {
             for order in orders_w_qty.orders.iter() {
                 if order.user_id == user_id {
                     let open_order = OpenOrder {
@@ -980,12 +1396,14 @@ impl OrderBook {
                         price: order.price,
                         quantity: order.quantity,
                         side: order.side,
+                        order_type: order.order_type,
+                        trigger_price: order.trigger_price,
                     };
                     open_orders.push(open_order);
                 }
             }
         }
-        
+
     }
 
     pub fn get_all_open_orders(
@@ -1006,49 +1424,181 @@ impl OrderBook {
             OrderSide::Sell, 
             &mut open_orders
         );
+
+        self.get_open_trigger_orders(user_id, &mut open_orders);
         
         Ok(open_orders)
     }
 
+    /// price levels of the side with the best price first, bids are descending and asks are ascending.
+    /// grouped levels round bids down and asks up, so a bucket never shows a better price than its orders.
     pub fn get_depth_on_side(
         &self,
         side:OrderSide,
+        limit:Option<usize>,
+        grouping:Option<Price>,
     ) -> Vec<[Decimal; 2]>{
-        let mut price_n_qty = vec![];
-        
+        let mut levels: BTreeMap<Price, Quantity> = BTreeMap::new();
+
         let price_w_orders_n_qty = match side {
             OrderSide::Buy => &self.bids,
             OrderSide::Sell => &self.asks,
         };
 
         for (price, orders_w_qty) in price_w_orders_n_qty {
-            let arr = [*price, orders_w_qty.total_quantity];
-            price_n_qty.push(arr);
+
+            if orders_w_qty.total_quantity == dec!(0) {
+                continue;
+            }
+
+            let level_price = match (grouping, side) {
+                (Some(grouping), OrderSide::Buy) => ((*price / grouping).floor() * grouping).normalize(),
+                (Some(grouping), OrderSide::Sell) => ((*price / grouping).ceil() * grouping).normalize(),
+                (None, _) => *price,
+            };
+
+            *levels.entry(level_price).or_insert(dec!(0)) += orders_w_qty.total_quantity;
         }
 
-        price_n_qty
+        let limit = limit.unwrap_or(usize::MAX);
+
+        let price_n_qty = levels.into_iter().map(|(price, quantity)| [price, quantity]);
 
+        match side {
+            OrderSide::Buy => price_n_qty.rev().take(limit).collect(),
+            OrderSide::Sell => price_n_qty.take(limit).collect(),
+        }
     }
 
-    pub fn get_depth(&self) -> Result<DepthResponse,EngineError>{
-        let bid_depth = self.get_depth_on_side(OrderSide::Buy);
-        let ask_depth = self.get_depth_on_side(OrderSide::Sell);
+    /// stamps every changed level with the next update id, None if no level has changed
+    pub fn get_depth_update(&mut self, price_w_depth:Option<PriceWithDepth>) -> Option<DepthUpdate>{
+
+        let depth = price_w_depth?;
+
+        let bids:Vec<[Decimal;2]> = depth.updated_bids.iter()
+        .map(|(price, qty)| [*price, *qty])
+        .collect();
+
+        let asks:Vec<[Decimal;2]> = depth.updated_asks.iter()
+        .map(|(price, qty)| [*price, *qty])
+        .collect();
+
+        if bids.is_empty() && asks.is_empty() {
+            return None;
+        }
+
+        let first_update_id = self.depth_update_id + 1;
+        self.depth_update_id += (bids.len() + asks.len()) as u64;
+
+        Some(DepthUpdate {
+            bids,
+            asks,
+            first_update_id,
+            last_update_id: self.depth_update_id,
+        })
+    }
+
+    pub fn get_depth(&self, payload:&DepthPayload) -> Result<DepthResponse,EngineError>{
+
+        if let Some(grouping) = payload.grouping {
+            if grouping <= dec!(0) || grouping % self.tick_size != dec!(0) {
+                println!("invalid depth grouping : {} for tick size : {}", grouping, self.tick_size);
+                return Err(EngineError::InvalidDepthGrouping);
+            }
+        }
+
+        let bid_depth = self.get_depth_on_side(OrderSide::Buy, payload.limit, payload.grouping);
+        let ask_depth = self.get_depth_on_side(OrderSide::Sell, payload.limit, payload.grouping);
 
         let depth = DepthResponse {
             asks: ask_depth,
-            bids: bid_depth
+            bids: bid_depth,
+            last_update_id: self.depth_update_id,
         };
 
         Ok(depth)
     }
    
+    /// timestamp is the time the message was accepted by the engine,
+    /// so replaying the message produces the same trades
+    pub fn get_order_status(order:&Order, order_placed:&OrderPlacedResponse) -> OrderStatus {
+        if order.quantity == order_placed.executed_quantity {
+            OrderStatus::Filled
+        } else if order_placed.cancelled_quantity > dec!(0) {
+            OrderStatus::Cancelled
+        } else {
+            OrderStatus::Open
+        }
+    }
+
+    /// collects the db and ws updates of the maker orders and the trades of the fills of the taker order
+    fn add_fill_updates(
+        &self,
+        taker_order:&Order,
+        fills:&[Fill],
+        timestamp:i64,
+        orders_to_update:&mut Vec<UpdateOrder>,
+        order_updates:&mut Vec<OrderUpdate>,
+        trades:&mut Vec<Trade>,
+    ){
+        for fill in fills.iter() {
+
+            // the maker order may have been filled partly before
+            let order_status = match fill.order_filled_quantity == fill.quantity {
+                true => OrderStatus::Filled,
+                false => OrderStatus::Open,
+            };
+
+            orders_to_update.push(UpdateOrder {
+                filled_quantity: fill.order_filled_quantity,
+                order_id: fill.order_id.clone(),
+                status:order_status,
+                timestamp,
+            });
+
+            order_updates.push(OrderUpdate {
+                user_id: fill.maker_id.clone(),
+                order_id: fill.order_id.clone(),
+                market: self.market.clone(),
+                side: taker_order.get_opposing_side(),
+                price: fill.price,
+                quantity: fill.quantity,
+                filled_quantity: fill.order_filled_quantity,
+                status: order_status,
+                timestamp,
+            });
+
+            let trade = Trade {
+                id: fill.trade_id,
+                price: fill.price,
+                quantity: fill.filled_quantity,
+                quote_qty: (fill.price * fill.filled_quantity).trunc_with_scale(u32::from(self.quote_decimals)),
+                market: self.market.clone(),
+                timestamp,
+                maker_fee: fill.maker_fee,
+                maker_fee_asset: fill.maker_fee_asset.clone(),
+                taker_fee: fill.taker_fee,
+                taker_fee_asset: fill.taker_fee_asset.clone(),
+                maker_order_id: fill.order_id.clone(),
+                taker_order_id: taker_order.id.clone(),
+                maker_user_id: fill.maker_id.clone(),
+                taker_user_id: taker_order.user_id.clone(),
+                is_buyer_maker: taker_order.side == OrderSide::Sell,
+            };
+
+            trades.push(trade);
+        }
+    }
+
     pub fn process(
             &mut self, 
             message_type:MessageFromApi, 
+            timestamp: i64,
             user_balances:Arc<Mutex<UserAssetBalance>>,
             redis:&RedisService,
     ){
-        let publish_on_channel;
+        // the id of the request, every response is published on it
+        let publish_on_channel = &message_type.get_channel_to_publish();
     
         match message_type {
 
@@ -1056,66 +1606,67 @@ impl OrderBook {
 
                 let mut order = Order::from_create_order_payload(payload);
 
-                let order_id = order.id.clone();
-                publish_on_channel = &order_id;
-
-                let res = self.process_order(&mut order, user_balances);
+                let res = self.process_order(&mut order, Arc::clone(&user_balances));
 
                 let mut order_to_add = None;
                 let mut orders_to_update:Vec<UpdateOrder> = vec![];
+                let mut order_updates:Vec<OrderUpdate> = vec![];
 
                 let mut trades = vec![];
                 let mut price_w_depth_to_update = None;
 
                 let message = match res {
 
-                    Ok((order_placed, price_w_depth)) => {
+                    Ok((executed, price_w_depth, triggered_orders)) => {
+
+                        let order_placed = executed.order_placed;
 
                         price_w_depth_to_update = Some(price_w_depth);
 
-                        let order_status = if order.quantity == order_placed.executed_quantity {OrderStatus::Filled} else {OrderStatus::Open};
+                        let order_status = match order.is_trigger_order() {
+                            true => OrderStatus::Pending,
+                            false => OrderBook::get_order_status(&order, &order_placed),
+                        };
 
                         let add_order = AddOrderToDb {
                             filled_quantity: order_placed.executed_quantity,
                             order_id: order.id.clone(),
+                            user_id: order.user_id.clone(),
+                            market: order.market.clone(),
+                            order_type: order.order_type,
                             price: order.price,
                             quantity: order.quantity,
                             side: order.side,
                             status: order_status,
+                            timestamp,
                         };
 
                         order_to_add = Some(add_order);
 
-                        orders_to_update = order_placed.fills.iter().map(|order|{
-
-                            let order_status;
-
-                            match order.filled_quantity == order.quantity {
-                                true => order_status = OrderStatus::Filled,
-                                false => order_status = OrderStatus::Open,
-                            }
-
-                            UpdateOrder {
-                                filled_quantity: order.filled_quantity,
-                                order_id: order.order_id.clone(),
-                                status:order_status,
-                            }
-                        }).collect();
+                        order_updates.push(self.get_order_update(&order, order_placed.executed_quantity, order_status, timestamp));
 
-                        for fill in order_placed.fills.iter(){
+                        self.add_fill_updates(&order, &executed.fills, timestamp, &mut orders_to_update, &mut order_updates, &mut trades);
 
-                            let now = Utc::now();
+                        // trigger orders are already in the db as pending
+                        for triggered in triggered_orders {
 
-                            let trade = Trade {
-                                id: fill.trade_id,
-                                price: fill.price,
-                                quantity: fill.filled_quantity,
-                                quote_qty: (order.price * fill.filled_quantity).trunc_with_scale(6),
-                                market: self.market.clone(),
-                                timestamp: now.timestamp_millis(),
+                            let (filled_quantity, status) = match &triggered.result {
+                                Ok(triggered_executed) => {
+                                    let triggered_placed = &triggered_executed.order_placed;
+                                    self.add_fill_updates(&triggered.order, &triggered_executed.fills, timestamp, &mut orders_to_update, &mut order_updates, &mut trades);
+                                    (triggered_placed.executed_quantity, OrderBook::get_order_status(&triggered.order, triggered_placed))
+                                },
+                                Err(_) => (triggered.order.filled, OrderStatus::Cancelled),
                             };
 
-                            trades.push(trade);
+                            orders_to_update.push(UpdateOrder {
+                                filled_quantity,
+                                order_id: triggered.order.id.clone(),
+                                status,
+                                timestamp,
+                            });
+
+                            order_updates.push(self.get_order_update(&triggered.order, filled_quantity, status, timestamp));
                         }
 
                         Ok(MessageFromEngine::OrderPlaced(order_placed))
@@ -1125,26 +1676,44 @@ impl OrderBook {
                     }
                 };
 
+                self.ticker_stats.add_trades(&trades);
+
+                let depth_update = self.get_depth_update(price_w_depth_to_update);
+                let is_book_updated = depth_update.is_some();
+
                 redis.publish_message_to_api(publish_on_channel, message);
                 redis.publish_ws_trade(&order.market, &trades);
-                redis.publish_ws_depth(&order.market, price_w_depth_to_update);
+                redis.publish_ws_depth(&order.market, depth_update);
+
+                if is_book_updated {
+                    redis.publish_ws_ticker(&self.get_ticker(timestamp));
+                }
+
+                let balance_updates = self.get_balance_updates(&self.get_updated_users(&order_updates, &trades), &user_balances, timestamp);
+
+                redis.publish_ws_order_updates(order_updates);
+                redis.publish_ws_fills(&trades);
+                redis.publish_ws_balance_updates(balance_updates);
+
                 redis.publish_trades_to_db(trades);
                 redis.update_db_orders(order_to_add, orders_to_update);
                 
             },
 
             MessageFromApi::CancelOrder(order_payload) => {
-                let order_id = order_payload.order_id.clone();
-                publish_on_channel = &order_id;
-
                 let market = order_payload.market.clone();
-                let cancel_order_res = self.cancel_order(order_payload, user_balances);
+                let user_id = order_payload.user_id.clone();
+                let cancel_order_res = self.cancel_order(order_payload, Arc::clone(&user_balances));
 
                 let mut updated_depths = None;
+                let mut order_updates = vec![];
+                let mut cancelled_orders = vec![];
 
                 let message = match cancel_order_res {
                     Ok((order_cancel, price_w_depth)) => {
                         updated_depths = Some(price_w_depth);
+                        cancelled_orders.push(order_cancel.order_id.clone());
+                        order_updates.push(self.get_cancelled_order_update(&user_id, &order_cancel, timestamp));
                         Ok(MessageFromEngine::OrderCancelled(order_cancel))
                     },
                     Err(e) => {
@@ -1152,20 +1721,34 @@ impl OrderBook {
                     }
                 };
 
+                let depth_update = self.get_depth_update(updated_depths);
+                let is_book_updated = depth_update.is_some();
+
                 redis.publish_message_to_api(publish_on_channel, message);
-                redis.publish_ws_depth(&market, updated_depths);
-                redis.publish_cancel_order_updates(vec![order_id]);
+                redis.publish_ws_depth(&market, depth_update);
+                if !cancelled_orders.is_empty() {
+                    redis.publish_cancel_order_updates(&user_id, cancelled_orders, timestamp);
+                }
+
+                if is_book_updated {
+                    redis.publish_ws_ticker(&self.get_ticker(timestamp));
+                }
+
+                let balance_updates = self.get_balance_updates(&self.get_updated_users(&order_updates, &[]), &user_balances, timestamp);
+
+                redis.publish_ws_order_updates(order_updates);
+                redis.publish_ws_balance_updates(balance_updates);
             },
 
             MessageFromApi::CancelAllOrders(payload) => {
 
-                publish_on_channel = &payload.user_id;
                 let market = &payload.market;
                 
                 let mut updated_depths = None;
-                let cancel_all_orders_res = self.cancel_all_orders(&payload.user_id, user_balances);
+                let cancel_all_orders_res = self.cancel_all_orders(&payload.user_id, Arc::clone(&user_balances));
 
                 let mut cancelled_orders = vec![];
+                let mut order_updates = vec![];
 
                 let message = cancel_all_orders_res.
                 map(|(orders, price_w_depth)|{
@@ -1174,36 +1757,55 @@ impl OrderBook {
 
                     for order in orders.iter() {
                         cancelled_orders.push(order.order_id.clone());
+                        order_updates.push(self.get_all_cancelled_order_update(&payload.user_id, order, timestamp));
                     }
 
                     MessageFromEngine::AllOrdersCancelled(orders)
                 } );
 
+                let depth_update = self.get_depth_update(updated_depths);
+                let is_book_updated = depth_update.is_some();
+
                 redis.publish_message_to_api(publish_on_channel, message);
-                redis.publish_ws_depth(market, updated_depths);
-                redis.publish_cancel_order_updates(cancelled_orders);
+                redis.publish_ws_depth(market, depth_update);
+                if !cancelled_orders.is_empty() {
+                    redis.publish_cancel_order_updates(&payload.user_id, cancelled_orders, timestamp);
+                }
+
+                if is_book_updated {
+                    redis.publish_ws_ticker(&self.get_ticker(timestamp));
+                }
+
+                let balance_updates = self.get_balance_updates(&self.get_updated_users(&order_updates, &[]), &user_balances, timestamp);
+
+                redis.publish_ws_order_updates(order_updates);
+                redis.publish_ws_balance_updates(balance_updates);
             },
 
             MessageFromApi::GetAllOpenOrders(payload) => {
 
-                publish_on_channel = &payload.user_id;
-
                 let get_all_orders_res = self.get_all_open_orders(&payload.user_id);
 
                 let message = get_all_orders_res.
-                map(|orders| MessageFromEngine::AllOpenOrders(orders));
+                map(MessageFromEngine::AllOpenOrders);
 
                 redis.publish_message_to_api(publish_on_channel, message);
             },
 
-            MessageFromApi::GetDepth(market) => {
-                publish_on_channel = &market;
+            MessageFromApi::GetDepth(payload) => {
 
-                let depth_res = self.get_depth();
+                let depth_res = self.get_depth(&payload);
 
-                let message = depth_res.map(|depth| MessageFromEngine::GetDepth(depth));
+                let message = depth_res.map(MessageFromEngine::GetDepth);
                 
                 redis.publish_message_to_api(publish_on_channel, message);
+            },
+
+            MessageFromApi::GetTicker(_) => {
+
+                let ticker = self.get_ticker(timestamp);
+
+                redis.publish_message_to_api(publish_on_channel, Ok(MessageFromEngine::Ticker(ticker)));
             }
         };
 
@@ -1211,3 +1813,436 @@ impl OrderBook {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::{Arc, Mutex}};
+    use common::{message::api::{CancelOrderPayload, CreateOrderPayload}, types::{fee::{FeeSchedule, FeeTier}, market::MarketConfig, order::{OrderSide, OrderType, Price, Quantity, TimeInForce}}};
+    use rust_decimal::{dec, Decimal};
+
+    use crate::{engine::{AssetBalance, UserAssetBalance}, errors::EngineError, order::Order};
+
+    use super::{ExecutedOrder, OrderBook};
+
+    const FEE_ACCOUNT: &str = "exchange_fees";
+
+    /// SOL_USDC with 3 base and 2 quote decimals, so 1 SOL is 1000 lamports and 1 USDC is 100
+    fn get_orderbook(fee_schedule: &FeeSchedule) -> OrderBook {
+
+        let config = MarketConfig {
+            base_asset: String::from("SOL"),
+            quote_asset: String::from("USDC"),
+            base_decimals: 3,
+            quote_decimals: 2,
+            tick_size: dec!(0.01),
+            lot_size: dec!(0.001),
+            min_quantity: dec!(0.001),
+            max_quantity: dec!(1000),
+            min_notional: dec!(0.01),
+            enabled: true,
+        };
+
+        OrderBook::new(&config, fee_schedule)
+    }
+
+    fn get_fee_schedule(maker_rate: Decimal, taker_rate: Decimal) -> FeeSchedule {
+        FeeSchedule {
+            fee_account: String::from(FEE_ACCOUNT),
+            default_tier: String::from("regular"),
+            tiers: HashMap::from([(String::from("regular"), FeeTier { maker_rate, taker_rate })]),
+            user_tiers: HashMap::new(),
+        }
+    }
+
+    /// 100 SOL and 10000 USDC for every user, nothing for the fee account
+    fn get_user_balances(user_ids: &[&str]) -> Arc<Mutex<UserAssetBalance>> {
+
+        let mut user_balances: UserAssetBalance = HashMap::new();
+
+        for user_id in user_ids {
+            user_balances.insert(user_id.to_string(), HashMap::from([
+                (String::from("SOL"), AssetBalance { available_amount: 100_000, locked_amount: 0 }),
+                (String::from("USDC"), AssetBalance { available_amount: 1_000_000, locked_amount: 0 }),
+            ]));
+        }
+
+        user_balances.insert(String::from(FEE_ACCOUNT), HashMap::new());
+
+        Arc::new(Mutex::new(user_balances))
+    }
+
+    /// (available, locked) lamports of the asset of the user
+    fn get_balance(user_balances: &Arc<Mutex<UserAssetBalance>>, user_id: &str, asset: &str) -> (u64, u64) {
+        user_balances.lock().unwrap()
+        .get(user_id)
+        .and_then(|balances| balances.get(asset))
+        .map_or((0, 0), |balance| (balance.available_amount, balance.locked_amount))
+    }
+
+    fn set_fee_account_balance(user_balances: &Arc<Mutex<UserAssetBalance>>, asset: &str, available_amount: u64) {
+        user_balances.lock().unwrap()
+        .entry(String::from(FEE_ACCOUNT))
+        .or_default()
+        .insert(asset.to_string(), AssetBalance { available_amount, locked_amount: 0 });
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn place(
+        orderbook: &mut OrderBook,
+        id: &str,
+        user_id: &str,
+        side: OrderSide,
+        order_type: OrderType,
+        price: Option<Price>,
+        quantity: Option<Quantity>,
+        quote_quantity: Option<Quantity>,
+        time_in_force: TimeInForce,
+        user_balances: &Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<ExecutedOrder, EngineError> {
+
+        let payload = CreateOrderPayload {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            side,
+            market: orderbook.market.clone(),
+            order_type,
+            price,
+            quantity,
+            quote_quantity,
+            time_in_force,
+            trigger_price: None,
+        };
+
+        let mut order = Order::from_create_order_payload(payload);
+
+        orderbook.process_order(&mut order, Arc::clone(user_balances)).map(|(executed, _, _)| executed)
+    }
+
+    fn place_limit(
+        orderbook: &mut OrderBook,
+        id: &str,
+        user_id: &str,
+        side: OrderSide,
+        price: Price,
+        quantity: Quantity,
+        user_balances: &Arc<Mutex<UserAssetBalance>>,
+    ) -> ExecutedOrder {
+        place(orderbook, id, user_id, side, OrderType::Limit, Some(price), Some(quantity), None, TimeInForce::Gtc, user_balances)
+        .unwrap_or_else(|e| panic!("Failed to place order : {} , error : {}", id, e))
+    }
+
+    fn cancel(orderbook: &mut OrderBook, order_id: &str, user_id: &str, user_balances: &Arc<Mutex<UserAssetBalance>>) {
+
+        let payload = CancelOrderPayload {
+            id: format!("cancel_{}", order_id),
+            market: orderbook.market.clone(),
+            order_id: order_id.to_string(),
+            user_id: user_id.to_string(),
+        };
+
+        if let Err(e) = orderbook.cancel_order(payload, Arc::clone(user_balances)) {
+            panic!("Failed to cancel order : {} , error : {}", order_id, e);
+        }
+    }
+
+    /// the index has exactly the resting orders of the levels, at their side and price
+    fn assert_order_index(orderbook: &OrderBook, expected_ids: &[&str]) {
+
+        let mut resting = vec![];
+
+        for (side, levels) in [(OrderSide::Buy, &orderbook.bids), (OrderSide::Sell, &orderbook.asks)] {
+            for (price, orders_w_qty) in levels {
+
+                assert!(!orders_w_qty.orders.is_empty(), "empty level : {} left on {:?}", price, side);
+
+                let remaining: Quantity = orders_w_qty.orders.iter().map(|order| order.quantity - order.filled).sum();
+                assert_eq!(orders_w_qty.total_quantity, remaining, "total quantity of level : {} on {:?}", price, side);
+
+                for order in orders_w_qty.orders.iter() {
+
+                    let location = orderbook.order_index.get(&order.id)
+                    .unwrap_or_else(|| panic!("order : {} is not in the index", order.id));
+
+                    assert_eq!(location.side, side, "side of order : {}", order.id);
+                    assert_eq!(location.price, *price, "price of order : {}", order.id);
+
+                    resting.push(order.id.as_str());
+                }
+            }
+        }
+
+        assert_eq!(orderbook.order_index.len(), resting.len(), "index has orders which are not on the book");
+
+        resting.sort();
+        let mut expected_ids = expected_ids.to_vec();
+        expected_ids.sort();
+
+        assert_eq!(resting, expected_ids);
+    }
+
+    #[test]
+    fn order_index_follows_partial_and_full_fills() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "a2", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "a3", "maker", OrderSide::Sell, dec!(101), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "b1", "maker", OrderSide::Buy, dec!(99), dec!(1), &user_balances);
+
+        assert_order_index(&orderbook, &["a1", "a2", "a3", "b1"]);
+
+        // fills a1 and half of a2
+        place_limit(&mut orderbook, "t1", "taker", OrderSide::Buy, dec!(100), dec!(1.5), &user_balances);
+        assert_order_index(&orderbook, &["a2", "a3", "b1"]);
+
+        // fills the rest of a2 and a3, the rest of t2 rests on the bids
+        place_limit(&mut orderbook, "t2", "taker", OrderSide::Buy, dec!(101), dec!(2), &user_balances);
+        assert_order_index(&orderbook, &["b1", "t2"]);
+
+        assert!(orderbook.asks.is_empty());
+        assert_eq!(orderbook.order_index.get("t2").map(|location| location.price), Some(dec!(101)));
+    }
+
+    #[test]
+    fn order_index_follows_cancels() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "other", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "a2", "other", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "a3", "maker", OrderSide::Sell, dec!(102), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "b1", "maker", OrderSide::Buy, dec!(99), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "b2", "other", OrderSide::Buy, dec!(98), dec!(1), &user_balances);
+
+        // partially filled order is cancelled from the middle of the book
+        place_limit(&mut orderbook, "t1", "taker", OrderSide::Buy, dec!(100), dec!(0.5), &user_balances);
+        cancel(&mut orderbook, "a1", "maker", &user_balances);
+        assert_order_index(&orderbook, &["a2", "a3", "b1", "b2"]);
+
+        // last order of its level
+        cancel(&mut orderbook, "b2", "other", &user_balances);
+        assert_order_index(&orderbook, &["a2", "a3", "b1"]);
+        assert!(!orderbook.bids.contains_key(&dec!(98)));
+
+        // orders of the other users stay in the index
+        if let Err(e) = orderbook.cancel_all_orders("maker", Arc::clone(&user_balances)) {
+            panic!("Failed to cancel all orders , error : {}", e);
+        }
+        assert_order_index(&orderbook, &["a2"]);
+
+        // the index rebuilt from the levels, as on a restore, is the same
+        let mut restored = orderbook.clone();
+        restored.order_index.clear();
+        restored.rebuild_order_index();
+        assert_order_index(&restored, &["a2"]);
+    }
+
+    #[test]
+    fn taker_buy_pays_the_fee_in_base_and_maker_in_quote() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0.001), dec!(0.002)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(10), &user_balances);
+        let executed = place_limit(&mut orderbook, "t1", "taker", OrderSide::Buy, dec!(100), dec!(10), &user_balances);
+
+        // 10000 SOL lamports to the taker less 20 fee, 100000 USDC lamports to the maker less 100 fee
+        assert_eq!(get_balance(&user_balances, "taker", "SOL"), (100_000 + 9_980, 0));
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000 - 100_000, 0));
+        assert_eq!(get_balance(&user_balances, "maker", "SOL"), (100_000 - 10_000, 0));
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 + 99_900, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "SOL"), (20, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (100, 0));
+
+        let fill = &executed.fills[0];
+        assert_eq!((fill.taker_fee, fill.taker_fee_asset.as_str()), (dec!(0.02), "SOL"));
+        assert_eq!((fill.maker_fee, fill.maker_fee_asset.as_str()), (dec!(1), "USDC"));
+
+        // fees are rounded down to the lamport, 700 * 0.002 = 1.4 and 7000 * 0.001 = 7
+        place_limit(&mut orderbook, "a2", "maker", OrderSide::Sell, dec!(100), dec!(0.7), &user_balances);
+        place_limit(&mut orderbook, "t2", "taker", OrderSide::Buy, dec!(100), dec!(0.7), &user_balances);
+
+        assert_eq!(get_balance(&user_balances, "taker", "SOL"), (100_000 + 9_980 + 699, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "SOL"), (21, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (107, 0));
+    }
+
+    #[test]
+    fn taker_sell_pays_the_fee_in_quote_and_maker_in_base() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0.001), dec!(0.002)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "b1", "maker", OrderSide::Buy, dec!(100), dec!(10), &user_balances);
+        let executed = place_limit(&mut orderbook, "t1", "taker", OrderSide::Sell, dec!(100), dec!(10), &user_balances);
+
+        // 100000 USDC lamports to the taker less 200 fee, 10000 SOL lamports to the maker less 10 fee
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000 + 99_800, 0));
+        assert_eq!(get_balance(&user_balances, "taker", "SOL"), (100_000 - 10_000, 0));
+        assert_eq!(get_balance(&user_balances, "maker", "SOL"), (100_000 + 9_990, 0));
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 - 100_000, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (200, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "SOL"), (10, 0));
+
+        let fill = &executed.fills[0];
+        assert_eq!((fill.taker_fee, fill.taker_fee_asset.as_str()), (dec!(2), "USDC"));
+        assert_eq!((fill.maker_fee, fill.maker_fee_asset.as_str()), (dec!(0.01), "SOL"));
+    }
+
+    #[test]
+    fn maker_rebate_is_paid_from_the_fee_account() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(-0.001), dec!(0.002)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+        set_fee_account_balance(&user_balances, "USDC", 1_000);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(10), &user_balances);
+        let executed = place_limit(&mut orderbook, "t1", "taker", OrderSide::Buy, dec!(100), dec!(10), &user_balances);
+
+        // rebate of 100 USDC lamports on top of the 100000 received
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 + 100_100, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (900, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "SOL"), (20, 0));
+        assert_eq!(executed.fills[0].maker_fee, dec!(-1));
+
+        // rebates are rounded towards zero, 3330 * 0.001 = 3.33
+        place_limit(&mut orderbook, "a2", "maker", OrderSide::Sell, dec!(100), dec!(0.333), &user_balances);
+        place_limit(&mut orderbook, "t2", "taker", OrderSide::Buy, dec!(100), dec!(0.333), &user_balances);
+
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 + 100_100 + 3_333, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (897, 0));
+    }
+
+    #[test]
+    fn maker_rebate_is_limited_to_the_fee_account_balance() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(-0.001), dec!(0.002)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+        set_fee_account_balance(&user_balances, "USDC", 50);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(10), &user_balances);
+        let executed = place_limit(&mut orderbook, "t1", "taker", OrderSide::Buy, dec!(100), dec!(10), &user_balances);
+
+        // rebate of 100 is cut to the 50 the fee account has, the fill reports what was paid
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 + 100_050, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (0, 0));
+        assert_eq!(executed.fills[0].maker_fee, dec!(-0.5));
+
+        // nothing left to pay the next rebate with
+        place_limit(&mut orderbook, "a2", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        place_limit(&mut orderbook, "t2", "taker", OrderSide::Buy, dec!(100), dec!(1), &user_balances);
+
+        assert_eq!(get_balance(&user_balances, "maker", "USDC"), (1_000_000 + 100_050 + 10_000, 0));
+        assert_eq!(get_balance(&user_balances, FEE_ACCOUNT, "USDC"), (0, 0));
+    }
+
+    #[test]
+    fn fok_order_which_fills_partially_is_rejected_without_changing_balances() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+        let balances_before = user_balances.lock().unwrap().clone();
+
+        let res = place(&mut orderbook, "t1", "taker", OrderSide::Buy, OrderType::Limit, Some(dec!(100)), Some(dec!(2)), None, TimeInForce::Fok, &user_balances);
+
+        assert!(matches!(res, Err(EngineError::PartialOrderFill)), "FOK order was not rejected : {:?}", res);
+
+        for (user_id, asset) in [("maker", "SOL"), ("maker", "USDC"), ("taker", "SOL"), ("taker", "USDC")] {
+            let before = balances_before.get(user_id).and_then(|balances| balances.get(asset))
+            .map(|balance| (balance.available_amount, balance.locked_amount));
+            assert_eq!(Some(get_balance(&user_balances, user_id, asset)), before, "{} balance of {}", asset, user_id);
+        }
+
+        assert_order_index(&orderbook, &["a1"]);
+        assert_eq!(orderbook.asks.get(&dec!(100)).map(|level| level.total_quantity), Some(dec!(1)));
+    }
+
+    #[test]
+    fn ioc_order_releases_the_balance_locked_for_its_unfilled_part() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+
+        let executed = place(&mut orderbook, "t1", "taker", OrderSide::Buy, OrderType::Limit, Some(dec!(101)), Some(dec!(3)), None, TimeInForce::Ioc, &user_balances)
+        .unwrap_or_else(|e| panic!("Failed to place IOC order , error : {}", e));
+
+        assert_eq!(executed.order_placed.executed_quantity, dec!(1));
+        assert_eq!(executed.order_placed.cancelled_quantity, dec!(2));
+
+        // only the 1 SOL filled at 100 is paid, the 30300 locked for 3 at 101 is released
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000 - 10_000, 0));
+        assert_eq!(get_balance(&user_balances, "taker", "SOL"), (100_000 + 1_000, 0));
+        assert_order_index(&orderbook, &[]);
+    }
+
+    #[test]
+    fn post_only_order_which_would_cross_is_rejected() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+
+        let res = place(&mut orderbook, "p1", "taker", OrderSide::Buy, OrderType::Limit, Some(dec!(100)), Some(dec!(1)), None, TimeInForce::PostOnly, &user_balances);
+
+        assert!(matches!(res, Err(EngineError::PostOnlyWouldCross)), "post only order was not rejected : {:?}", res);
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000, 0));
+        assert_order_index(&orderbook, &["a1"]);
+
+        // below the best ask it rests on the book
+        let res = place(&mut orderbook, "p2", "taker", OrderSide::Buy, OrderType::Limit, Some(dec!(99.99)), Some(dec!(1)), None, TimeInForce::PostOnly, &user_balances);
+
+        assert!(res.is_ok(), "post only order was rejected : {:?}", res);
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000 - 9_999, 9_999));
+        assert_order_index(&orderbook, &["a1", "p2"]);
+    }
+
+    #[test]
+    fn fok_market_order_sized_by_quote_is_rejected() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(1), &user_balances);
+
+        let res = place(&mut orderbook, "m1", "taker", OrderSide::Buy, OrderType::Market, None, None, Some(dec!(50)), TimeInForce::Fok, &user_balances);
+
+        assert!(matches!(res, Err(EngineError::InvalidTimeInForce)), "FOK market order by quote was not rejected : {:?}", res);
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000, 0));
+        assert_order_index(&orderbook, &["a1"]);
+    }
+
+    #[test]
+    fn market_order_sized_by_quote_follows_the_quantity_limits() {
+
+        let mut orderbook = get_orderbook(&get_fee_schedule(dec!(0), dec!(0)));
+        let user_balances = get_user_balances(&["maker", "taker"]);
+
+        orderbook.min_quantity = dec!(0.5);
+        orderbook.max_quantity = dec!(2);
+
+        place_limit(&mut orderbook, "a1", "maker", OrderSide::Sell, dec!(100), dec!(2), &user_balances);
+        place_limit(&mut orderbook, "a2", "maker", OrderSide::Sell, dec!(101), dec!(2), &user_balances);
+
+        // 250 USDC sweeps 2.495 SOL
+        let res = place(&mut orderbook, "m1", "taker", OrderSide::Buy, OrderType::Market, None, None, Some(dec!(250)), TimeInForce::Ioc, &user_balances);
+        assert!(matches!(res, Err(EngineError::AboveMaxQuantity)), "market order above max quantity was not rejected : {:?}", res);
+
+        // 20 USDC sweeps 0.2 SOL
+        let res = place(&mut orderbook, "m2", "taker", OrderSide::Buy, OrderType::Market, None, None, Some(dec!(20)), TimeInForce::Ioc, &user_balances);
+        assert!(matches!(res, Err(EngineError::BelowMinQuantity)), "market order below min quantity was not rejected : {:?}", res);
+
+        assert_eq!(get_balance(&user_balances, "taker", "USDC"), (1_000_000, 0));
+        assert_order_index(&orderbook, &["a1", "a2"]);
+
+        let res = place(&mut orderbook, "m3", "taker", OrderSide::Buy, OrderType::Market, None, None, Some(dec!(150)), TimeInForce::Ioc, &user_balances);
+        assert!(matches!(&res, Ok(executed) if executed.order_placed.executed_quantity == dec!(1.5)), "market order within the limits was not filled : {:?}", res);
+    }
+}
@@ -1,14 +1,25 @@
-use common::{channel::{DB_CHANNEL, ORDER_CHANNEL, USER_CHANNEL}, message::{db_filler::{AddOrderToDb, DbFillerMessage, Trade, UpdateOrder}, engine::{MessageFromEngine, UserMessageFromEngine}, ws::{DepthUpdate, TradeUpdate, WsMessage}}};
+use std::sync::mpsc;
+
+use common::{channel::{DB_CHANNEL, ORDER_CHANNEL, USER_CHANNEL}, message::{db_filler::{AddOrderToDb, DbFillerMessage, LedgerEntry, Trade, UpdateOrder}, engine::{MessageFromEngine, UserMessageFromEngine}, ws::{BalanceUpdate, DepthUpdate, FillUpdate, OrderUpdate, TradeUpdate, WsMessage}}, types::ticker::Ticker};
 use r2d2_redis::{r2d2::{self, Pool, PooledConnection}, redis::{Commands, RedisError}, RedisConnectionManager};
-use rust_decimal::Decimal;
 
-use crate::{errors::EngineError, orderbook::PriceWithDepth};
+use crate::errors::EngineError;
 
 pub type RedisResponse = Result<(), r2d2_redis::redis::RedisError>;
 
 #[derive(Debug, Clone)]
 pub struct RedisService {
-    pool: Pool<RedisConnectionManager>,
+    pool: Option<Pool<RedisConnectionManager>>,
+    // when set, outputs are sent here instead of redis (used while replaying the journal)
+    recorder: Option<mpsc::Sender<String>>,
+    // only the db filler messages are sent, used while recovering the journal on startup
+    db_filler_only: bool,
+}
+
+impl Default for RedisService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RedisService {
@@ -17,11 +28,22 @@ impl RedisService {
         let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_e|String::from("redis://127.0.0.1:6379"));
         let manager = RedisConnectionManager::new(redis_url).expect("Failed to create redis manager");
         let pool = r2d2::Pool::builder().build(manager).expect("Failed to create Redis Pool");        
-        Self { pool }
+        Self { pool: Some(pool), recorder: None, db_filler_only: false }
+    }
+
+    /// publishes to the api and the ws are dropped, the db filler messages are still sent,
+    /// so the db gets the orders, trades and ledger entries which were lost on a crash
+    pub fn recovering() -> Self {
+        Self { db_filler_only: true, ..Self::new() }
+    }
+
+    /// every output is sent on the tx as a line of `<COMMAND> <channel> <payload>`
+    pub fn recorder(tx: mpsc::Sender<String>) -> Self {
+        Self { pool: None, recorder: Some(tx), db_filler_only: false }
     }
 
     pub fn get_conn(&self) -> Option<PooledConnection<RedisConnectionManager>>{
-        let conn_res = self.pool.get();
+        let conn_res = self.pool.as_ref()?.get();
 
         if let Err(e) = conn_res {
             println!("Error : {e} while acquiring redis connection from pool in update_db orders");
@@ -32,14 +54,70 @@ impl RedisService {
         Some(conn)
     }
 
-    fn publish_to_db_filler(&self, mut conn: PooledConnection<RedisConnectionManager>, message:DbFillerMessage){
+    fn record(&self, command:&str, channel:&str, payload:&str) -> bool {
+        match &self.recorder {
+            Some(tx) => {
+                if let Err(e) = tx.send(format!("{} {} {}", command, channel, payload)) {
+                    println!("Error : {} while recording output on channel : {}", e, channel);
+                }
+                true
+            },
+            None => false
+        }
+    }
+
+    fn publish(&self, channel:&str, payload:String) -> RedisResponse {
+
+        if self.db_filler_only || self.record("PUBLISH", channel, &payload) {
+            return Ok(());
+        }
+
+        match self.get_conn() {
+            Some(mut conn) => conn.publish(channel, payload),
+            None => Ok(()),
+        }
+    }
+
+    fn push_to_queue(&self, queue:&str, payload:String) -> RedisResponse {
+
+        if self.record("LPUSH", queue, &payload) {
+            return Ok(());
+        }
+
+        match self.get_conn() {
+            Some(mut conn) => conn.lpush(queue, payload),
+            None => Ok(()),
+        }
+    }
+
+    /// sends an output of a `recorder` to redis, the outputs of the journaled messages
+    /// are recorded while the journal is held and sent once it is released
+    pub fn send_recorded(&self, output:&str){
+
+        let mut parts = output.splitn(3, ' ');
+
+        let res = match (parts.next(), parts.next(), parts.next()) {
+            (Some("PUBLISH"), Some(channel), Some(payload)) => self.publish(channel, payload.to_string()),
+            (Some("LPUSH"), Some(queue), Some(payload)) => self.push_to_queue(queue, payload.to_string()),
+            _ => {
+                println!("invalid recorded output : {}", output);
+                return;
+            }
+        };
+
+        if let Err(e) = res {
+            println!("Error : {} while sending recorded output : {}", e, output);
+        }
+    }
+
+    fn publish_to_db_filler(&self, message:DbFillerMessage){
 
         let serialized_message = serde_json::to_string(&message);
 
         match serialized_message{
             Ok(serialized) => {
 
-                let redis_res:RedisResponse = conn.lpush(DB_CHANNEL, serialized);
+                let redis_res:RedisResponse = self.push_to_queue(DB_CHANNEL, serialized);
 
                 if let Err(e) = redis_res {
                     println!("Error:{} while publishing to db channel from update_db_orders", e);
@@ -54,7 +132,6 @@ impl RedisService {
     fn publish_to_ws(
         &self, 
         channel:&str,
-        mut conn: PooledConnection<RedisConnectionManager>, 
         message:WsMessage
     ){
         let serialized_message = serde_json::to_string(&message);
@@ -62,7 +139,7 @@ impl RedisService {
         match serialized_message{
             Ok(serialized) => {
 
-                let redis_res:RedisResponse = conn.publish(channel, serialized);
+                let redis_res:RedisResponse = self.publish(channel, serialized);
 
                 if let Err(e) = redis_res {
                     println!("Error:{} while publishing to wss", e);
@@ -79,47 +156,31 @@ impl RedisService {
         add_order: Option<AddOrderToDb>,
         update_orders: Vec<UpdateOrder>
     ){
-        let conn_res = self.get_conn();
-
-        if let None = conn_res {
-            return;
-        }
-
-        if let None = add_order {
+        if add_order.is_none() {
             println!("some error occurred add_order is None while publishing db orders");
             println!("publishing the updated_orders to db_filler  : {:?}", update_orders)
         }
 
-        let conn = conn_res.unwrap();
         let message = DbFillerMessage::AddAndUpdateOrders { add_order, update_orders };
-        self.publish_to_db_filler(conn, message);
+        self.publish_to_db_filler(message);
 
     }
 
     
     pub fn publish_trades_to_db(&self, trades: Vec<Trade>){
-        let conn_res = self.get_conn();
-
-        if let None = conn_res {
-            return;
-        }
-
-        let conn = conn_res.unwrap();
         let message = DbFillerMessage::AddTrade(trades);
-        self.publish_to_db_filler(conn, message);
+        self.publish_to_db_filler(message);
         
     }
 
-    pub fn publish_cancel_order_updates(&self, orders:Vec<String>){
-        let conn_res = self.get_conn();
-
-        if let None = conn_res {
-            return;
-        }
+    pub fn publish_cancel_order_updates(&self, user_id:&str, order_ids:Vec<String>, timestamp:i64){
+        let message = DbFillerMessage::UpdateCancelOrders { user_id: user_id.to_string(), order_ids, timestamp };
+        self.publish_to_db_filler(message);
+    }
 
-        let conn = conn_res.unwrap();
-        let message = DbFillerMessage::UpdateCancelOrders(orders);
-        self.publish_to_db_filler(conn, message);
+    pub fn publish_ledger_entry_to_db(&self, entry: LedgerEntry){
+        let message = DbFillerMessage::AddLedgerEntry(entry);
+        self.publish_to_db_filler(message);
     }
     
     pub fn publish_message_to_api(
@@ -127,8 +188,6 @@ impl RedisService {
         channel:&str, 
         message_res:Result<MessageFromEngine, EngineError>){
 
-        let mut conn = self.pool.get().unwrap();
-
         let serialized = match message_res {
             Err(e) => {
                 e.to_error_response().serialize_as_err()
@@ -138,7 +197,7 @@ impl RedisService {
             }
         };
 
-        let res:RedisResponse = conn.publish(channel, serialized);        
+        let res:RedisResponse = self.publish(channel, serialized);        
 
         if let Err(e) = res {
             println!("Error while publishing message to api : {}", e);
@@ -147,7 +206,7 @@ impl RedisService {
     }
 
     pub fn get_message_from_api(&self) -> Option<Option<String>>{
-        let mut conn = self.pool.get().unwrap(); 
+        let mut conn = self.get_conn()?; 
 
         let res: Result<Option<String>,RedisError> = conn.rpop(ORDER_CHANNEL);
     
@@ -164,7 +223,7 @@ impl RedisService {
 
     pub fn get_user_message_from_api(&self) -> Option<String>{
 
-        let mut conn = self.pool.get().unwrap(); 
+        let mut conn = self.get_conn()?; 
 
         let res: Result<Option<String>,RedisError> = conn.rpop(USER_CHANNEL);
     
@@ -185,8 +244,6 @@ impl RedisService {
         message_res:Result<UserMessageFromEngine, EngineError>,
     ) {
 
-        let mut conn = self.pool.get().unwrap();
-
         let serialized = match message_res {
             Err(e) => {
                 e.to_error_response().serialize_as_err()
@@ -196,7 +253,7 @@ impl RedisService {
             }
         };
 
-        let res:RedisResponse = conn.publish(channel, serialized);        
+        let res:RedisResponse = self.publish(&channel, serialized);        
 
         if let Err(e) = res {
             println!("Error while publishing message to api : {}", e);
@@ -204,70 +261,89 @@ impl RedisService {
 
     }
 
-    pub fn publish_ws_trade(&self, market:&str, trades:&Vec<Trade>){
+    pub fn publish_ws_trade(&self, market:&str, trades:&[Trade]){
 
         let channel = format!("trade@{}", market);
 
-        let conn_res = self.get_conn();
-
-        if let None = conn_res {
-            return;
-        }
-
-        let conn = conn_res.unwrap();
-
-        let trade_updates: Vec<TradeUpdate> = trades.iter().map(|trade| TradeUpdate {
-            e: "trade".to_string(),
-            p: trade.price,
-            q: trade.quantity,
-            s: trade.market.clone(),
-            t: trade.id,
+        let trade_updates: Vec<TradeUpdate> = trades.iter().map(|trade| {
+
+            let (buyer_order_id, seller_order_id) = match trade.is_buyer_maker {
+                true => (&trade.maker_order_id, &trade.taker_order_id),
+                false => (&trade.taker_order_id, &trade.maker_order_id),
+            };
+
+            TradeUpdate {
+                e: "trade".to_string(),
+                p: trade.price,
+                q: trade.quantity,
+                s: trade.market.clone(),
+                t: trade.id,
+                b: buyer_order_id.clone(),
+                a: seller_order_id.clone(),
+                m: trade.is_buyer_maker,
+            }
         }).collect();
 
         let message = WsMessage::Trade(trade_updates);
 
-        self.publish_to_ws(&channel, conn, message);
+        self.publish_to_ws(&channel, message);
         
     }
 
+    pub fn publish_ws_ticker(&self, ticker:&Ticker){
+
+        let channel = format!("ticker@{}", ticker.market);
+
+        self.publish_to_ws(&channel, WsMessage::Ticker(ticker.clone()));
+    }
+
     pub fn publish_ws_depth(
         &self, 
         market:&str,
-        price_w_depth: Option<PriceWithDepth>
+        depth_update: Option<DepthUpdate>
     ){
 
         let channel = format!("depth@{}", market);
 
-        let conn_res = self.get_conn();
+        // an empty update would use no update id, so nothing is sent
+        let depth_update = match depth_update {
+            Some(depth_update) => depth_update,
+            None => {
+                println!("no depth to update to wss!");
+                return;
+            }
+        };
 
-        if let None = conn_res {
-            return;
-        }
+        let message = WsMessage::Depth { depth: depth_update };
 
-        let conn = conn_res.unwrap();
+        self.publish_to_ws(&channel, message);
+    }
 
-        let depth_update = match price_w_depth {
-            Some(depth) => {
+    pub fn publish_ws_order_updates(&self, order_updates:Vec<OrderUpdate>){
 
-                let bids:Vec<[Decimal;2]> = depth.updated_bids.iter()
-                .map(|(price, qty)| [*price, *qty])
-                .collect();
-                
-                let asks:Vec<[Decimal;2]> = depth.updated_asks.iter()
-                .map(|(price, qty)| [*price, *qty])
-                .collect();
+        for order_update in order_updates {
+            let channel = format!("order@{}", order_update.user_id);
+            self.publish_to_ws(&channel, WsMessage::Order(order_update));
+        }
+    }
 
-                DepthUpdate::from_value(bids, asks)
-            },
-            None => {
-                println!("no depth to update to wss!");
-                DepthUpdate::new()
+    /// fills of the maker and the taker of every trade
+    pub fn publish_ws_fills(&self, trades:&[Trade]){
+
+        for trade in trades {
+            for fill in FillUpdate::from_trade(trade) {
+                let channel = format!("fill@{}", fill.user_id);
+                self.publish_to_ws(&channel, WsMessage::Fill(fill));
             }
-        };
+        }
+    }
 
-        let message = WsMessage::Depth { depth: depth_update };
+    pub fn publish_ws_balance_updates(&self, balance_updates:Vec<BalanceUpdate>){
 
-        self.publish_to_ws(&channel, conn, message);
+        for balance_update in balance_updates {
+            let channel = format!("balance@{}", balance_update.user_id);
+            self.publish_to_ws(&channel, WsMessage::Balance(balance_update));
+        }
     }
 
 }
@@ -0,0 +1,159 @@
+use std::{fs::{self, File}, io::{self, Write}, path::PathBuf, time::{Duration, Instant}};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::UserAssetBalance, orderbook::OrderBook};
+
+// only the latest few snapshots are kept on disk, older ones are pruned after every save
+pub const SNAPSHOTS_TO_KEEP: usize = 3;
+const SNAPSHOT_PREFIX: &str = "snapshot_";
+const SNAPSHOT_EXTENSION: &str = "json";
+
+/// Point in time state of the engine,
+/// every orderbook along with the balances of all the users
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: i64,
+    // sequence of the last journal entry applied before taking the snapshot
+    #[serde(default)]
+    pub last_seq: u64,
+    pub orderbooks: Vec<OrderBook>,
+    pub user_balances: UserAssetBalance,
+}
+
+impl Snapshot {
+    pub fn new(last_seq: u64, orderbooks: Vec<OrderBook>, user_balances: UserAssetBalance) -> Self {
+        Self {
+            timestamp: Utc::now().timestamp_millis(),
+            last_seq,
+            orderbooks,
+            user_balances,
+        }
+    }
+}
+
+/// Saves the snapshots periodically to the
+/// snapshot dir and recovers the latest one on startup
+pub struct SnapshotManager {
+    dir: PathBuf,
+    interval: Duration,
+    last_saved_at: Instant,
+}
+
+impl SnapshotManager {
+
+    pub fn new() -> Self {
+        let dir = std::env::var("SNAPSHOT_DIR").unwrap_or_else(|_|String::from("snapshots"));
+
+        let interval_secs = std::env::var("SNAPSHOT_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(60);
+
+        Self {
+            dir: PathBuf::from(dir),
+            interval: Duration::from_secs(interval_secs),
+            last_saved_at: Instant::now(),
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last_saved_at.elapsed() >= self.interval
+    }
+
+    fn get_snapshot_files(&self) -> io::Result<Vec<PathBuf>> {
+
+        let mut files = vec![];
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            let is_snapshot = path.extension().is_some_and(|ext| ext == SNAPSHOT_EXTENSION)
+            && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(SNAPSHOT_PREFIX));
+
+            if is_snapshot {
+                files.push(path);
+            }
+        }
+
+        // file names carry the timestamp, so sorting by name sorts them by time
+        files.sort();
+
+        Ok(files)
+    }
+
+    /// writes the snapshot to a temp file and renames it after it is on the disk,
+    /// so a crash while writing never leaves a half written snapshot behind
+    pub fn save(&mut self, snapshot: &Snapshot) -> io::Result<PathBuf> {
+
+        self.last_saved_at = Instant::now();
+
+        fs::create_dir_all(&self.dir)?;
+
+        let serialized = serde_json::to_vec(snapshot)?;
+
+        let file_name = format!("{}{:020}.{}", SNAPSHOT_PREFIX, snapshot.timestamp, SNAPSHOT_EXTENSION);
+        let path = self.dir.join(file_name);
+        let tmp_path = path.with_extension("tmp");
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serialized)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, &path)?;
+
+        // the rename is durable only once the dir is synced
+        File::open(&self.dir)?.sync_all()?;
+
+        let files = self.get_snapshot_files()?;
+
+        if files.len() > SNAPSHOTS_TO_KEEP {
+            for file in &files[..files.len() - SNAPSHOTS_TO_KEEP] {
+                if let Err(e) = fs::remove_file(file) {
+                    println!("Error : {} while removing old snapshot : {:?}", e, file);
+                }
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// returns the latest readable snapshot,
+    /// None if there are no snapshots saved yet
+    pub fn load_latest(&self) -> Option<Snapshot> {
+
+        let files = match self.get_snapshot_files() {
+            Ok(files) => files,
+            Err(e) => {
+                println!("No snapshots found in {:?} : {}", self.dir, e);
+                return None;
+            }
+        };
+
+        // fallback to the older ones if the latest one is corrupted
+        for file in files.iter().rev() {
+
+            let snapshot = fs::read(file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_slice::<Snapshot>(&content).map_err(|e| e.to_string()));
+
+            match snapshot {
+                Ok(snapshot) => {
+                    println!("Recovered snapshot : {:?} taken at : {} with last seq : {}", file, snapshot.timestamp, snapshot.last_seq);
+                    return Some(snapshot);
+                },
+                Err(e) => {
+                    println!("Error : {} while reading snapshot : {:?}", e, file);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Default for SnapshotManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
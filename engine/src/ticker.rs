@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+use common::{message::db_filler::Trade, types::{kline::{Kline, KlineInterval}, order::{Price, Quantity}, ticker::Ticker}};
+use rust_decimal::{dec, Decimal};
+use serde::{Deserialize, Serialize};
+
+use crate::orderbook::OrderBook;
+
+const TICKER_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Trades of the last 24 hours of a market, aggregated in one minute klines
+/// so the memory stays the same however many trades there are
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TickerStats {
+    klines: VecDeque<Kline>,
+}
+
+impl TickerStats {
+
+    pub fn add_trades(&mut self, trades:&[Trade]) {
+
+        for trade in trades {
+
+            let interval = KlineInterval::OneMinute;
+
+            match self.klines.back_mut() {
+                Some(kline) if kline.open_time == interval.get_open_time(trade.timestamp) => {
+                    kline.add_trade(trade.price, trade.quantity, trade.quote_qty);
+                },
+                _ => {
+                    let kline = Kline::new(&trade.market, interval, trade.timestamp, trade.price, trade.quantity, trade.quote_qty);
+                    self.klines.push_back(kline);
+                }
+            }
+
+            // klines which can't be in the window of any later time
+            while self.klines.front().is_some_and(|kline| kline.close_time < trade.timestamp - TICKER_WINDOW_MS) {
+                self.klines.pop_front();
+            }
+        }
+    }
+
+    /// klines with trades in the 24 hours ending at the timestamp, the window
+    /// is accurate to the minute as the trades are bucketed by the minute
+    fn get_window(&self, timestamp:i64) -> impl Iterator<Item = &Kline> {
+        self.klines.iter().filter(move |kline| kline.close_time >= timestamp - TICKER_WINDOW_MS)
+    }
+}
+
+impl OrderBook {
+
+    pub fn get_best_bid(&self) -> Option<(Price, Quantity)> {
+        self.bids.iter()
+        .rev()
+        .find(|(_, orders)| orders.total_quantity > dec!(0))
+        .map(|(price, orders)| (*price, orders.total_quantity))
+    }
+
+    pub fn get_best_ask(&self) -> Option<(Price, Quantity)> {
+        self.asks.iter()
+        .find(|(_, orders)| orders.total_quantity > dec!(0))
+        .map(|(price, orders)| (*price, orders.total_quantity))
+    }
+
+    /// ticker of the 24 hours ending at the timestamp
+    pub fn get_ticker(&self, timestamp:i64) -> Ticker {
+
+        let mut open_price = None;
+        let mut high_price = self.last_price;
+        let mut low_price = self.last_price;
+        let mut volume = dec!(0);
+        let mut quote_volume = dec!(0);
+        let mut trade_count = 0;
+
+        for kline in self.ticker_stats.get_window(timestamp) {
+
+            if open_price.is_none() {
+                open_price = Some(kline.open);
+                high_price = kline.high;
+                low_price = kline.low;
+            }
+
+            high_price = high_price.max(kline.high);
+            low_price = low_price.min(kline.low);
+            volume += kline.volume;
+            quote_volume += kline.quote_volume;
+            trade_count += kline.trade_count;
+        }
+
+        // no trades in the last 24 hours, so the price hasn't changed
+        let open_price = open_price.unwrap_or(self.last_price);
+        let price_change = self.last_price - open_price;
+
+        let price_change_percent = match open_price == dec!(0) {
+            true => dec!(0),
+            false => (price_change / open_price * Decimal::ONE_HUNDRED).round_dp(2),
+        };
+
+        let best_bid = self.get_best_bid();
+        let best_ask = self.get_best_ask();
+
+        Ticker {
+            market: self.market.clone(),
+            last_price: self.last_price,
+            open_price,
+            high_price,
+            low_price,
+            volume: volume.normalize(),
+            quote_volume: quote_volume.normalize(),
+            price_change,
+            price_change_percent: price_change_percent.normalize(),
+            trade_count,
+            best_bid_price: best_bid.map(|(price, _)| price),
+            best_bid_quantity: best_bid.map(|(_, quantity)| quantity),
+            best_ask_price: best_ask.map(|(price, _)| price),
+            best_ask_quantity: best_ask.map(|(_, quantity)| quantity),
+            timestamp,
+        }
+    }
+}
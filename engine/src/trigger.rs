@@ -0,0 +1,249 @@
+use std::sync::{Arc, Mutex};
+use common::{message::engine::{CancelAllOrders, OpenOrder, OrderCancelledResponse, OrderPlacedResponse}, types::order::{OrderSide, OrderType}};
+use rust_decimal::dec;
+
+use crate::{engine::UserAssetBalance, errors::EngineError, order::Order, orderbook::{ExecutedOrder, OrderBook, PriceWithDepth}};
+
+/// Trigger order which was placed as a regular order
+/// after the last price reached its trigger price
+#[derive(Debug)]
+pub struct TriggeredOrder {
+    pub order: Order,
+    pub result: Result<ExecutedOrder, EngineError>,
+}
+
+impl OrderBook {
+
+    /// stops trigger when the last price moves against the position,
+    /// take profits trigger when it moves in favour of the position
+    pub fn is_triggered(&self, order:&Order) -> bool {
+
+        let trigger_price = match order.trigger_price {
+            Some(trigger_price) => trigger_price,
+            None => return false,
+        };
+
+        // no trades on the market yet
+        if self.last_price == dec!(0) {
+            return false;
+        }
+
+        match (order.order_type, order.side) {
+            (OrderType::StopMarket | OrderType::StopLimit, OrderSide::Buy) => self.last_price >= trigger_price,
+            (OrderType::StopMarket | OrderType::StopLimit, OrderSide::Sell) => self.last_price <= trigger_price,
+            (OrderType::TakeProfitMarket | OrderType::TakeProfitLimit, OrderSide::Buy) => self.last_price <= trigger_price,
+            (OrderType::TakeProfitMarket | OrderType::TakeProfitLimit, OrderSide::Sell) => self.last_price >= trigger_price,
+            _ => false,
+        }
+    }
+
+    pub fn validate_trigger_order(&self, order:&Order) -> Result<(), EngineError> {
+
+        let trigger_price = order.trigger_price.unwrap_or(dec!(0));
+
+        if trigger_price <= dec!(0) || trigger_price % self.tick_size != dec!(0) || self.is_triggered(order) {
+            println!("invalid trigger price : {} of order : {} with last price : {}", trigger_price, order.id, self.last_price);
+            return Err(EngineError::InvalidTriggerPrice);
+        }
+
+        // the balance is locked at placement, so it must be known before the price is
+        if order.get_execution_type() == OrderType::Market {
+
+            let is_valid = match order.side {
+                OrderSide::Buy => order.quote_quantity.is_some(),
+                OrderSide::Sell => order.quote_quantity.is_none(),
+            };
+
+            if !is_valid {
+                return Err(EngineError::InvalidTriggerOrder);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// amount of the asset locked for the trigger order while it waits in the trigger book
+    pub fn get_trigger_lock_amount(&self, order:&Order) -> Result<u64, EngineError> {
+
+        let amount = match (order.get_execution_type(), order.side) {
+            (OrderType::Market, OrderSide::Buy) => {
+                OrderBook::to_lamports(order.quote_quantity.unwrap_or(dec!(0)), self.get_quote_lamports())
+            },
+            (_, OrderSide::Buy) => {
+                order.price.checked_mul(order.quantity).and_then(|notional| OrderBook::to_lamports(notional, self.get_quote_lamports()))
+            },
+            (_, OrderSide::Sell) => {
+                OrderBook::to_lamports(order.quantity, self.get_base_lamports())
+            }
+        };
+
+        amount.ok_or_else(||{
+            println!("amount to lock for trigger order : {} doesn't fit in lamports", order.id);
+            EngineError::AboveMaxNotional
+        })
+    }
+
+    /// locks the balance of the trigger order and adds it to the trigger book
+    pub fn add_trigger_order(
+        &mut self,
+        order:&Order,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<OrderPlacedResponse, EngineError>{
+
+        let amount = self.get_trigger_lock_amount(order)?;
+
+        self.lock_user_balance(&order.user_id, self.get_locked_asset(order.side), amount, user_balances)?;
+
+        println!("adding order : {} to the trigger book with trigger price : {:?}", order.id, order.trigger_price);
+
+        self.trigger_orders.push(order.clone());
+
+        Ok(OrderPlacedResponse {
+            order_id: order.id.clone(),
+            executed_quantity: dec!(0),
+            cancelled_quantity: dec!(0),
+            fills: vec![],
+        })
+    }
+
+    /// places the trigger orders reached by the last price, till no more orders are triggered,
+    /// as the triggered orders can move the last price again
+    pub fn execute_trigger_orders(
+        &mut self,
+        price_w_depth:&mut PriceWithDepth,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Vec<TriggeredOrder>{
+
+        let mut triggered_orders = vec![];
+
+        while let Some(index) = self.trigger_orders.iter().position(|order| self.is_triggered(order)) {
+
+            let mut order = self.trigger_orders.remove(index);
+
+            println!("order : {} triggered at last price : {}", order.id, self.last_price);
+
+            let result = self.execute_triggered_order(&mut order, price_w_depth, user_balances);
+
+            triggered_orders.push(TriggeredOrder { order, result });
+        }
+
+        triggered_orders
+    }
+
+    /// the balance was locked at placement, so the order is only checked against
+    /// the current book and then executed. if it can't be placed its balance is unlocked.
+    fn execute_triggered_order(
+        &mut self,
+        order:&mut Order,
+        price_w_depth:&mut PriceWithDepth,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<ExecutedOrder, EngineError>{
+
+        let locked_amount = self.get_trigger_lock_amount(order)?;
+
+        order.order_type = order.get_execution_type();
+
+        let mut can_execute = self.check_time_in_force(order);
+
+        if can_execute.is_ok() && order.order_type == OrderType::Market {
+            can_execute = self.size_market_order(order).map(|_| ());
+        }
+
+        if let Err(e) = can_execute {
+            println!("cancelling triggered order : {} , error : {}", order.id, e);
+            self.unlock_user_balance(&order.user_id, self.get_locked_asset(order.side), locked_amount, user_balances)?;
+            return Err(e);
+        }
+
+        self.execute_order(order, locked_amount, price_w_depth, user_balances)
+    }
+
+    /// removes the order from the trigger book and unlocks its balance,
+    /// None if the order is not in the trigger book
+    pub fn cancel_trigger_order(
+        &mut self,
+        order_id:&String,
+        user_id:&String,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<Option<OrderCancelledResponse>, EngineError>{
+
+        let index = match self.trigger_orders.iter().position(|order| order.id == *order_id) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        if self.trigger_orders[index].user_id != *user_id {
+            println!("{} cannot cancel the trigger order : {}", user_id, order_id);
+            return Err(EngineError::MismatchUser);
+        }
+
+        let order = self.trigger_orders.remove(index);
+
+        let amount = self.get_trigger_lock_amount(&order)?;
+        self.unlock_user_balance(&order.user_id, self.get_locked_asset(order.side), amount, user_balances)?;
+
+        let order_cancelled = OrderCancelledResponse {
+            order_id: order.id,
+            quantity: order.quantity,
+            executed_quantity: order.filled,
+            side: order.side,
+            price: order.price,
+        };
+
+        println!("cancelled trigger order : {:?}", order_cancelled);
+
+        Ok(Some(order_cancelled))
+    }
+
+    pub fn cancel_all_trigger_orders(
+        &mut self,
+        user_id:&str,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+    ) -> Result<Vec<CancelAllOrders>, EngineError>{
+
+        let (orders, remaining): (Vec<Order>, Vec<Order>) = self.trigger_orders
+        .drain(..)
+        .partition(|order| order.user_id == user_id);
+
+        self.trigger_orders = remaining;
+
+        let mut cancelled_orders = vec![];
+
+        for order in orders {
+
+            let amount = self.get_trigger_lock_amount(&order)?;
+            self.unlock_user_balance(&order.user_id, self.get_locked_asset(order.side), amount, user_balances)?;
+
+            println!("trigger order cancelled: {:?}", order);
+
+            cancelled_orders.push(
+                CancelAllOrders {
+                    order_id: order.id,
+                    quantity: order.quantity,
+                    executed_quantity: order.filled,
+                    side: order.side,
+                    price: order.price,
+                }
+            );
+        }
+
+        Ok(cancelled_orders)
+    }
+
+    pub fn get_open_trigger_orders(&self, user_id:&str, open_orders:&mut Vec<OpenOrder>) {
+
+        for order in self.trigger_orders.iter() {
+            if order.user_id == user_id {
+                open_orders.push(OpenOrder {
+                    order_id: order.id.clone(),
+                    quantity: order.quantity,
+                    executed_quantity: order.filled,
+                    side: order.side,
+                    price: order.price,
+                    order_type: order.order_type,
+                    trigger_price: order.trigger_price,
+                });
+            }
+        }
+    }
+}
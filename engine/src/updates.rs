@@ -0,0 +1,97 @@
+use std::{collections::BTreeSet, sync::{Arc, Mutex}};
+use common::{message::{db_filler::{OrderStatus, Trade}, engine::{AssetAndBalance, CancelAllOrders, OrderCancelledResponse}, ws::{BalanceUpdate, OrderUpdate}}, types::order::Quantity};
+
+use crate::{engine::UserAssetBalance, order::Order, orderbook::OrderBook};
+
+/// Updates of the orders and balances of the users,
+/// sent on their private ws channels
+impl OrderBook {
+
+    pub fn get_order_update(&self, order:&Order, filled_quantity:Quantity, status:OrderStatus, timestamp:i64) -> OrderUpdate {
+        OrderUpdate {
+            user_id: order.user_id.clone(),
+            order_id: order.id.clone(),
+            market: self.market.clone(),
+            side: order.side,
+            price: order.price,
+            quantity: order.quantity,
+            filled_quantity,
+            status,
+            timestamp,
+        }
+    }
+
+    pub fn get_cancelled_order_update(&self, user_id:&str, order_cancelled:&OrderCancelledResponse, timestamp:i64) -> OrderUpdate {
+        OrderUpdate {
+            user_id: user_id.to_string(),
+            order_id: order_cancelled.order_id.clone(),
+            market: self.market.clone(),
+            side: order_cancelled.side,
+            price: order_cancelled.price,
+            quantity: order_cancelled.quantity,
+            filled_quantity: order_cancelled.executed_quantity,
+            status: OrderStatus::Cancelled,
+            timestamp,
+        }
+    }
+
+    pub fn get_all_cancelled_order_update(&self, user_id:&str, order_cancelled:&CancelAllOrders, timestamp:i64) -> OrderUpdate {
+        OrderUpdate {
+            user_id: user_id.to_string(),
+            order_id: order_cancelled.order_id.clone(),
+            market: self.market.clone(),
+            side: order_cancelled.side,
+            price: order_cancelled.price,
+            quantity: order_cancelled.quantity,
+            filled_quantity: order_cancelled.executed_quantity,
+            status: OrderStatus::Cancelled,
+            timestamp,
+        }
+    }
+
+    /// users whose balances of the market assets were changed by the order updates and the trades,
+    /// the fee account takes the fees of every trade
+    pub fn get_updated_users(&self, order_updates:&[OrderUpdate], trades:&[Trade]) -> BTreeSet<String> {
+
+        let mut user_ids: BTreeSet<String> = order_updates.iter().map(|update| update.user_id.clone()).collect();
+
+        if !trades.is_empty() {
+            user_ids.insert(self.fee_schedule.fee_account.clone());
+        }
+
+        user_ids
+    }
+
+    /// balances of the base and the quote asset of the users
+    pub fn get_balance_updates(
+        &self,
+        user_ids:&BTreeSet<String>,
+        user_balances:&Arc<Mutex<UserAssetBalance>>,
+        timestamp:i64
+    ) -> Vec<BalanceUpdate> {
+
+        let guard = user_balances.lock().unwrap();
+
+        user_ids.iter().filter_map(|user_id| {
+
+            let assets_balance = guard.get(user_id)?;
+
+            let balances = [(&self.base_asset, self.base_decimals), (&self.quote_asset, self.quote_decimals)]
+            .into_iter()
+            .map(|(asset, decimals)| {
+                match assets_balance.get(asset) {
+                    Some(balance) => AssetAndBalance::from_lamports(asset, decimals, balance.available_amount, balance.locked_amount),
+                    None => AssetAndBalance::from_lamports(asset, decimals, 0, 0),
+                }
+            })
+            .collect();
+
+            Some(BalanceUpdate {
+                user_id: user_id.clone(),
+                balances,
+                timestamp,
+            })
+        })
+        .collect()
+    }
+}
@@ -1,34 +1,62 @@
-use std::sync::{Arc, Mutex};
-use common::message::{api::UserMessageFromApi, engine::{AssetAndBalance, UserBalanceResponse, UserMessageFromEngine}};
+use std::{collections::HashMap, sync::{mpsc, Arc, Mutex}};
+use chrono::Utc;
+use common::{message::{api::{BalanceChangePayload, CreateUserPayload, UserBalancePayload, UserMessageFromApi}, db_filler::{LedgerEntry, LedgerEntryType}, engine::{AssetAndBalance, BalanceUpdatedResponse, UserBalanceResponse, UserCreatedResponse, UserMessageFromEngine}, ws::BalanceUpdate}, types::market::MarketRegistry};
+use rust_decimal::{dec, prelude::ToPrimitive, Decimal};
 
-use crate::{engine::UserAssetBalance, errors::EngineError, services::redis::RedisService};
+use crate::{engine::{AssetBalance, UserAssetBalance}, errors::EngineError, journal::{Journal, JournalMessage}, services::redis::RedisService};
 
-pub struct User;
+/// Handles the requests which are not for an orderbook.
+/// Balance changes are journaled before they are applied, same as the orders.
+pub struct User {
+    asset_decimals: HashMap<String, u8>,
+}
 
 impl User {
 
+    pub fn new(registry: &MarketRegistry) -> Self {
+        Self {
+            asset_decimals: registry.get_asset_decimals(),
+        }
+    }
+
     pub fn process_user_message(
+        &self,
         message:String,
+        journal: &Arc<Mutex<Journal>>,
         user_balances: Arc<Mutex<UserAssetBalance>>,
         redis_service: &RedisService
     ){
         let try_user_message = UserMessageFromApi::try_deserialized(&message);
-        
+
         match try_user_message {
             Ok(user_message) => {
 
-                let channel;
-                let res;
+                if !user_message.is_balance_change() {
+                    self.process(0, Utc::now().timestamp_millis(), user_message, &user_balances, redis_service);
+                    return;
+                }
 
-                match user_message {
-                    UserMessageFromApi::Balance(user_id) => {
-                        channel = format!("{}-balance", user_id);
-                        res = User::get_user_asset_balance(user_id, user_balances);
+                // the journal is held till the balances are updated,
+                // so a snapshot never has a journaled entry which is not applied yet.
+                // the outputs are recorded and sent to redis once the journal is released
+                let (outputs_tx, outputs_rx) = mpsc::channel::<String>();
+                let mut journal_guard = journal.lock().unwrap();
+
+                match journal_guard.append(JournalMessage::User(user_message.clone())) {
+                    Ok(entry) => {
+                        self.process(entry.seq, entry.timestamp, user_message, &user_balances, &RedisService::recorder(outputs_tx));
+                    },
+                    Err(e) => {
+                        println!("Error : {} while writing user message to the journal", e);
+                        redis_service.publish_user_message_to_api(user_message.get_channel_to_publish(), Err(EngineError::InternalError));
                     }
                 }
 
-                redis_service.publish_user_message_to_api(channel, res);
+                drop(journal_guard);
 
+                for output in outputs_rx.iter() {
+                    redis_service.send_recorded(&output);
+                }
             },
             Err(e) => {
                 println!("error : {e} while deserializing user message");
@@ -36,13 +64,51 @@ impl User {
         }
     }
 
+    /// applies the user message and publishes the result, seq is the journal
+    /// sequence of the message and is used as the id of the ledger entries
+    pub fn process(
+        &self,
+        seq: u64,
+        timestamp: i64,
+        user_message: UserMessageFromApi,
+        user_balances: &Arc<Mutex<UserAssetBalance>>,
+        redis_service: &RedisService
+    ){
+        let channel = user_message.get_channel_to_publish();
+
+        let res = match user_message {
+            UserMessageFromApi::Balance(payload) => {
+                self.get_user_asset_balance(payload, Arc::clone(user_balances))
+            },
+            UserMessageFromApi::CreateUser(payload) => {
+                User::create_user(payload, user_balances)
+            },
+            UserMessageFromApi::Deposit(payload) => {
+                self.update_balance(seq, timestamp, payload, LedgerEntryType::Deposit, user_balances, redis_service)
+            },
+            UserMessageFromApi::Withdraw(payload) => {
+                self.update_balance(seq, timestamp, payload, LedgerEntryType::Withdraw, user_balances, redis_service)
+            },
+        };
+
+        redis_service.publish_user_message_to_api(channel, res);
+    }
+
+    /// balances of all the assets held by the user, or only of the given asset
     pub fn get_user_asset_balance(
-        user_id:String, 
+        &self,
+        payload: UserBalancePayload,
         user_balances: Arc<Mutex<UserAssetBalance>>
     ) -> Result<UserMessageFromEngine, EngineError>{
 
+        if let Some(asset) = &payload.asset {
+            if !self.asset_decimals.contains_key(asset) {
+                return Err(EngineError::InvalidAsset);
+            }
+        }
+
         let guard = user_balances.lock().unwrap();
-        let try_assets_balance = guard.get(&user_id);
+        let try_assets_balance = guard.get(&payload.user_id);
 
         match try_assets_balance {
 
@@ -50,17 +116,25 @@ impl User {
 
                 let mut balances = vec![];
 
-                for (asset, balance) in assets_balance {
-                    balances.push(
-                        AssetAndBalance {
-                            asset: asset.to_owned(),
-                            balance: balance.available_amount
+                match &payload.asset {
+                    Some(asset) => {
+                        // the asset was never deposited or traded by the user
+                        let empty_balance = AssetBalance { available_amount: 0, locked_amount: 0 };
+                        let balance = assets_balance.get(asset).unwrap_or(&empty_balance);
+
+                        balances.push(self.get_asset_and_balance(asset, balance));
+                    },
+                    None => {
+                        for (asset, balance) in assets_balance {
+                            balances.push(self.get_asset_and_balance(asset, balance));
                         }
-                    );
+                    }
                 }
 
+                balances.sort_by(|a, b| a.asset.cmp(&b.asset));
+
                 let user_balance_res = UserBalanceResponse {
-                    user_id,
+                    user_id: payload.user_id,
                     balances,
                 };
 
@@ -74,5 +148,136 @@ impl User {
         }
     }
 
+    fn get_asset_and_balance(&self, asset:&str, balance:&AssetBalance) -> AssetAndBalance {
+
+        // assets of the markets removed from the registry are shown in lamports
+        let decimals = self.asset_decimals.get(asset).copied().unwrap_or(0);
+
+        AssetAndBalance::from_lamports(asset, decimals, balance.available_amount, balance.locked_amount)
+    }
+
+    pub fn create_user(
+        payload: CreateUserPayload,
+        user_balances: &Arc<Mutex<UserAssetBalance>>
+    ) -> Result<UserMessageFromEngine, EngineError>{
+
+        let mut guard = user_balances.lock().unwrap();
+
+        if guard.contains_key(&payload.user_id) {
+            println!("user : {} already exists", payload.user_id);
+            return Err(EngineError::UserAlreadyExists);
+        }
+
+        // balances of the assets are added on the first deposit
+        guard.insert(payload.user_id.clone(), HashMap::new());
+
+        println!("created user : {}", payload.user_id);
+
+        Ok(UserMessageFromEngine::UserCreated(UserCreatedResponse { user_id: payload.user_id }))
+    }
+
+    /// converts the amount to the lamports of the asset
+    fn get_amount_in_lamports(&self, asset:&str, amount:Decimal) -> Result<(u64, u64), EngineError> {
+
+        let decimals = match self.asset_decimals.get(asset) {
+            Some(decimals) => *decimals,
+            None => return Err(EngineError::InvalidAsset),
+        };
+
+        if amount <= dec!(0) || amount.normalize().scale() > u32::from(decimals) {
+            println!("invalid amount : {} for asset : {}", amount, asset);
+            return Err(EngineError::InvalidAmount);
+        }
+
+        let lamports = 10_u64.pow(u32::from(decimals));
+
+        let amount = (amount * Decimal::from(lamports)).to_u64().ok_or(EngineError::InvalidAmount)?;
+
+        Ok((amount, lamports))
+    }
+
+    /// deposits add to the available balance, withdrawals can only take from it.
+    /// funds locked in open orders can't be withdrawn till the orders are cancelled.
+    fn update_balance(
+        &self,
+        seq: u64,
+        timestamp: i64,
+        payload: BalanceChangePayload,
+        entry_type: LedgerEntryType,
+        user_balances: &Arc<Mutex<UserAssetBalance>>,
+        redis_service: &RedisService
+    ) -> Result<UserMessageFromEngine, EngineError>{
+
+        let (amount, lamports) = self.get_amount_in_lamports(&payload.asset, payload.amount)?;
+
+        let mut guard = user_balances.lock().unwrap();
+
+        let assets_balance = match guard.get_mut(&payload.user_id) {
+            Some(assets_balance) => assets_balance,
+            None => return Err(EngineError::UserNotFound),
+        };
+
+        let balance = assets_balance.entry(payload.asset.clone()).or_insert(AssetBalance {
+            available_amount: 0,
+            locked_amount: 0,
+        });
+
+        match entry_type {
+            LedgerEntryType::Deposit => {
+                balance.available_amount = balance.available_amount.checked_add(amount).ok_or(EngineError::InvalidAmount)?;
+            },
+            LedgerEntryType::Withdraw => {
+
+                if balance.available_amount < amount {
 
-}
\ No newline at end of file
+                    println!("user : {} can't withdraw {} {}, available : {} locked : {}", payload.user_id, amount, payload.asset, balance.available_amount, balance.locked_amount);
+
+                    if balance.available_amount + balance.locked_amount >= amount {
+                        return Err(EngineError::FundsLocked);
+                    }
+
+                    return Err(EngineError::InsufficientBalance);
+                }
+
+                balance.available_amount -= amount;
+            }
+        }
+
+        let available_amount = Decimal::from(balance.available_amount) / Decimal::from(lamports);
+
+        let decimals = self.asset_decimals.get(&payload.asset).copied().unwrap_or(0);
+        let asset_balance = AssetAndBalance::from_lamports(&payload.asset, decimals, balance.available_amount, balance.locked_amount);
+
+        drop(guard);
+
+        println!("{} of {} {} for user : {}", entry_type, payload.amount, payload.asset, payload.user_id);
+
+        let entry = LedgerEntry {
+            id: seq,
+            user_id: payload.user_id.clone(),
+            asset: payload.asset.clone(),
+            amount: payload.amount.normalize(),
+            entry_type,
+            timestamp,
+        };
+
+        redis_service.publish_ledger_entry_to_db(entry);
+
+        redis_service.publish_ws_balance_updates(vec![BalanceUpdate {
+            user_id: payload.user_id.clone(),
+            balances: vec![asset_balance],
+            timestamp,
+        }]);
+
+        let res = BalanceUpdatedResponse {
+            ledger_id: seq,
+            user_id: payload.user_id,
+            asset: payload.asset,
+            amount: payload.amount.normalize(),
+            entry_type,
+            available_amount: available_amount.normalize(),
+        };
+
+        Ok(UserMessageFromEngine::BalanceUpdated(res))
+    }
+}
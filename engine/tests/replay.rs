@@ -0,0 +1,125 @@
+use std::{env, fs, sync::{mpsc, Arc, Mutex}, thread};
+
+use common::types::{fee::FeeSchedule, market::MarketRegistry};
+use engine::{engine::{Engine, MarketMessage}, journal::Journal, services::redis::RedisService, user::User};
+
+// (queue, message) as they are pushed by the api, in the order they are accepted
+const MESSAGES: [(&str, &str); 14] = [
+    ("orders", r#"{"CreateOrder":{"id":"s1","user_id":"random2","side":"Sell","market":"SOL_USDC","order_type":"Limit","price":"100","quantity":"1"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"s2","user_id":"random2","side":"Sell","market":"SOL_USDC","order_type":"Limit","price":"101","quantity":"2"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"m1","user_id":"random1","side":"Buy","market":"SOL_USDC","order_type":"Market","price":null,"quantity":null,"quote_quantity":"150"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"i1","user_id":"random1","side":"Buy","market":"SOL_USDC","order_type":"Limit","price":"100","quantity":"1","time_in_force":"IOC"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"f1","user_id":"random1","side":"Buy","market":"SOL_USDC","order_type":"Limit","price":"101","quantity":"5","time_in_force":"FOK"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"t1","user_id":"random32","side":"Sell","market":"SOL_USDC","order_type":"StopMarket","price":null,"quantity":"1","trigger_price":"100.5"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"b1","user_id":"random2","side":"Buy","market":"SOL_USDC","order_type":"Limit","price":"99","quantity":"1"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"m2","user_id":"random1","side":"Sell","market":"SOL_USDC","order_type":"Market","price":null,"quantity":"0.5"}}"#),
+    ("orders", r#"{"CancelOrder":{"id":"c1","market":"SOL_USDC","order_id":"s2","user_id":"random1"}}"#),
+    ("orders", r#"{"CancelOrder":{"id":"c2","market":"SOL_USDC","order_id":"s2","user_id":"random2"}}"#),
+    ("orders", r#"{"CreateOrder":{"id":"b2","user_id":"random2","side":"Buy","market":"SOL_USDC","order_type":"Limit","price":"98","quantity":"3"}}"#),
+    ("orders", r#"{"CancelAllOrders":{"id":"ca1","market":"SOL_USDC","user_id":"random2"}}"#),
+    ("user", r#"{"Deposit":{"id":"dep1","user_id":"random1","asset":"USDC","amount":"5"}}"#),
+    ("user", r#"{"Withdraw":{"id":"wd1","user_id":"random1","asset":"SOL","amount":"1.5"}}"#),
+];
+
+fn load_config() -> (MarketRegistry, FeeSchedule) {
+    (MarketRegistry::load(), FeeSchedule::load())
+}
+
+/// runs the messages through the orderbook and user threads the way main does,
+/// every output is recorded instead of being sent to redis
+fn run_live() -> Vec<String> {
+
+    let (registry, fee_schedule) = load_config();
+    let engine = Engine::init(&registry, &fee_schedule, None);
+    let user = User::new(&registry);
+
+    let journal = Arc::new(Mutex::new(Journal::open(engine.last_seq).expect("Failed to open the journal")));
+    let user_balances = Arc::new(Mutex::new(engine.user_balances));
+
+    let (outputs_tx, outputs_rx) = mpsc::channel::<String>();
+    let recorder = RedisService::recorder(outputs_tx);
+
+    let mut markets_tx = Engine::init_market_tx();
+    let mut handles = vec![];
+
+    for orderbook in engine.orderbooks {
+
+        let (tx, rx) = mpsc::channel::<MarketMessage>();
+        markets_tx.insert(orderbook.market.clone(), tx);
+
+        let user_balances = Arc::clone(&user_balances);
+        let recorder = recorder.clone();
+
+        handles.push(thread::spawn(move ||{
+            Engine::run_orderbook(orderbook, rx, user_balances, recorder);
+        }));
+    }
+
+    for (queue, message) in MESSAGES {
+        match queue {
+            "orders" => Engine::dispatch(message, &journal, &markets_tx, &recorder),
+            _ => user.process_user_message(message.to_string(), &journal, Arc::clone(&user_balances), &recorder),
+        }
+    }
+
+    // the orderbook threads stop once their tx is dropped, after publishing their outputs
+    drop(markets_tx);
+
+    for handle in handles {
+        handle.join().expect("orderbook thread panicked");
+    }
+
+    drop(recorder);
+
+    outputs_rx.into_iter().collect()
+}
+
+/// replays the journal from the start on the current thread, same as `engine replay`
+fn replay() -> Vec<String> {
+
+    let (registry, fee_schedule) = load_config();
+    let mut engine = Engine::init(&registry, &fee_schedule, None);
+    let user = User::new(&registry);
+
+    let entries = Journal::read_entries_after(0).expect("Failed to read the journal");
+    assert_eq!(entries.len(), MESSAGES.len());
+
+    let (outputs_tx, outputs_rx) = mpsc::channel::<String>();
+
+    engine.replay(entries, &user, &RedisService::recorder(outputs_tx));
+
+    outputs_rx.into_iter().collect()
+}
+
+#[test]
+fn replay_outputs_are_identical_to_the_live_run() {
+
+    let dir = env::temp_dir().join(format!("engine_replay_test_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    env::set_var("JOURNAL_PATH", dir.join("orders.log"));
+    env::set_var("MARKETS_CONFIG", concat!(env!("CARGO_MANIFEST_DIR"), "/../markets.json"));
+    env::set_var("FEES_CONFIG", concat!(env!("CARGO_MANIFEST_DIR"), "/../fees.json"));
+
+    let live = run_live();
+    let first = replay();
+    let second = replay();
+
+    let _ = fs::remove_dir_all(&dir);
+
+    // every kind of output of the sequence is there, so the comparisons below are not vacuous
+    for expected in [r#""AddTrade":[{"#, r#""UpdateCancelOrders""#, r#""entry_type":"Deposit""#, r#""entry_type":"Withdraw""#, "trade@SOL_USDC"] {
+        assert!(first.iter().any(|output| output.contains(expected)), "no output with : {}", expected);
+    }
+
+    assert_eq!(first, second);
+
+    // the orderbook threads publish after releasing the journal, so the outputs
+    // of different threads can be interleaved differently in the live run
+    let mut live = live;
+    let mut first = first;
+    live.sort();
+    first.sort();
+
+    assert_eq!(live, first);
+}
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE "trade" DROP COLUMN maker_fee;
+ALTER TABLE "trade" DROP COLUMN maker_fee_asset;
+ALTER TABLE "trade" DROP COLUMN taker_fee;
+ALTER TABLE "trade" DROP COLUMN taker_fee_asset;
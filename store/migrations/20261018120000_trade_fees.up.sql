@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TABLE "trade" ADD COLUMN maker_fee VARCHAR(255) NOT NULL DEFAULT '0';
+ALTER TABLE "trade" ADD COLUMN maker_fee_asset VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "trade" ADD COLUMN taker_fee VARCHAR(255) NOT NULL DEFAULT '0';
+ALTER TABLE "trade" ADD COLUMN taker_fee_asset VARCHAR(255) NOT NULL DEFAULT '';
@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS "ledger"(
+    id bigint PRIMARY KEY NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    asset VARCHAR(255) NOT NULL,
+    amount VARCHAR(255) NOT NULL,
+    entry_type VARCHAR(255) NOT NULL,
+    created_at bigint NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ledger_user_id_idx ON "ledger"(user_id, created_at);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE "trade" DROP CONSTRAINT trade_pkey;
+ALTER TABLE "trade" ADD PRIMARY KEY (id);
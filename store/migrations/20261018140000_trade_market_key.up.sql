@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- trade ids are unique only within a market
+ALTER TABLE "trade" DROP CONSTRAINT trade_pkey;
+ALTER TABLE "trade" ADD PRIMARY KEY (market, id);
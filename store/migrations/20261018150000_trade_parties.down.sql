@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE "trade" DROP COLUMN maker_order_id;
+ALTER TABLE "trade" DROP COLUMN taker_order_id;
+ALTER TABLE "trade" DROP COLUMN maker_user_id;
+ALTER TABLE "trade" DROP COLUMN taker_user_id;
+ALTER TABLE "trade" DROP COLUMN is_buyer_maker;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE "trade" ADD COLUMN maker_order_id VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "trade" ADD COLUMN taker_order_id VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "trade" ADD COLUMN maker_user_id VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "trade" ADD COLUMN taker_user_id VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "trade" ADD COLUMN is_buyer_maker BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS order_user_id_market_created_at_idx;
+DROP INDEX IF EXISTS order_user_id_created_at_idx;
+
+ALTER TABLE "order" DROP COLUMN user_id;
+ALTER TABLE "order" DROP COLUMN market;
+ALTER TABLE "order" DROP COLUMN order_type;
+ALTER TABLE "order" DROP COLUMN created_at;
+ALTER TABLE "order" DROP COLUMN updated_at;
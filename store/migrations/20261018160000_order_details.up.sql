@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TABLE "order" ADD COLUMN user_id VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "order" ADD COLUMN market VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "order" ADD COLUMN order_type VARCHAR(255) NOT NULL DEFAULT 'Limit';
+ALTER TABLE "order" ADD COLUMN created_at bigint NOT NULL DEFAULT 0;
+ALTER TABLE "order" ADD COLUMN updated_at bigint NOT NULL DEFAULT 0;
+
+-- order history of a user, latest first, optionally for a single market
+CREATE INDEX IF NOT EXISTS order_user_id_created_at_idx ON "order"(user_id, created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS order_user_id_market_created_at_idx ON "order"(user_id, market, created_at DESC, id DESC);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS trade_maker_user_id_idx;
+DROP INDEX IF EXISTS trade_taker_user_id_idx;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- fills of a user, latest first
+CREATE INDEX IF NOT EXISTS trade_maker_user_id_idx ON "trade"(maker_user_id, matched_at DESC);
+CREATE INDEX IF NOT EXISTS trade_taker_user_id_idx ON "trade"(taker_user_id, matched_at DESC);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS "kline"(
+    market VARCHAR(255) NOT NULL,
+    "interval" VARCHAR(16) NOT NULL,
+    open_time bigint NOT NULL,
+    close_time bigint NOT NULL,
+    open VARCHAR(255) NOT NULL,
+    high VARCHAR(255) NOT NULL,
+    low VARCHAR(255) NOT NULL,
+    close VARCHAR(255) NOT NULL,
+    volume VARCHAR(255) NOT NULL,
+    quote_volume VARCHAR(255) NOT NULL,
+    trade_count bigint NOT NULL,
+    PRIMARY KEY (market, "interval", open_time)
+);
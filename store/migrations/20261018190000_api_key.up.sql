@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS "api_key"(
+    api_key VARCHAR(255) PRIMARY KEY NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    created_at bigint NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS api_key_user_id_idx ON "api_key"(user_id);
@@ -0,0 +1,12 @@
+-- Add down migration script here
+-- the keys can't be recovered from their hashes, so every key has to be created again
+DELETE FROM "api_key";
+
+ALTER TABLE "api_key" DROP CONSTRAINT IF EXISTS api_key_id_key;
+ALTER TABLE "api_key" DROP COLUMN IF EXISTS allowed_ips;
+ALTER TABLE "api_key" DROP COLUMN IF EXISTS scopes;
+ALTER TABLE "api_key" DROP COLUMN IF EXISTS label;
+ALTER TABLE "api_key" DROP COLUMN IF EXISTS key_prefix;
+ALTER TABLE "api_key" DROP COLUMN IF EXISTS id;
+
+ALTER TABLE "api_key" RENAME COLUMN key_hash TO api_key;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- only the sha256 hash of the api key is kept, the key is shown once when it is created
+ALTER TABLE "api_key" RENAME COLUMN api_key TO key_hash;
+
+ALTER TABLE "api_key" ADD COLUMN id VARCHAR(255);
+ALTER TABLE "api_key" ADD COLUMN key_prefix VARCHAR(255);
+ALTER TABLE "api_key" ADD COLUMN label VARCHAR(255) NOT NULL DEFAULT '';
+ALTER TABLE "api_key" ADD COLUMN scopes TEXT[] NOT NULL DEFAULT '{read,trade,withdraw}';
+ALTER TABLE "api_key" ADD COLUMN allowed_ips TEXT[];
+
+UPDATE "api_key" SET
+    id = gen_random_uuid()::text,
+    key_prefix = left(key_hash, 8),
+    key_hash = encode(sha256(key_hash::bytea), 'hex');
+
+ALTER TABLE "api_key" ALTER COLUMN id SET NOT NULL;
+ALTER TABLE "api_key" ALTER COLUMN key_prefix SET NOT NULL;
+ALTER TABLE "api_key" ALTER COLUMN scopes DROP DEFAULT;
+ALTER TABLE "api_key" ADD CONSTRAINT api_key_id_key UNIQUE (id);
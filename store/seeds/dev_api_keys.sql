@@ -0,0 +1,8 @@
+-- keys of the dummy users seeded by the engine, ONLY for trying the exchange locally.
+-- their secrets are public, never run this on a database which is reachable by anyone else.
+-- psql $DATABASE_URL -f store/seeds/dev_api_keys.sql
+INSERT INTO "api_key" (id, key_hash, key_prefix, secret, user_id, label, scopes, allowed_ips, created_at) VALUES
+    (gen_random_uuid()::text, encode(sha256('random1-dev-key'::bytea), 'hex'), 'random1-', 'random1-dev-secret', 'random1', 'dev', '{read,trade,withdraw,deposit}', NULL, 0),
+    (gen_random_uuid()::text, encode(sha256('random2-dev-key'::bytea), 'hex'), 'random2-', 'random2-dev-secret', 'random2', 'dev', '{read,trade,withdraw,deposit}', NULL, 0),
+    (gen_random_uuid()::text, encode(sha256('random32-dev-key'::bytea), 'hex'), 'random32', 'random32-dev-secret', 'random32', 'dev', '{read,trade,withdraw,deposit}', NULL, 0)
+ON CONFLICT (key_hash) DO NOTHING;
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, Pool, Postgres};
+
+/// Key of a user to sign the api requests with its secret.
+/// Only the hash of the key is stored, the key itself is shown once when it is created.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    // first characters of the key, so the user can tell the keys apart
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub user_id: String,
+    pub label: String,
+    pub scopes: Vec<String>,
+    // the key can be used from any ip when it is None
+    pub allowed_ips: Option<Vec<String>>,
+    pub created_at: i64,
+}
+
+impl ApiKey {
+
+    pub async fn add_api_key(api_key:&ApiKey, pool:&Pool<Postgres>) -> Result<(), Error>{
+
+        sqlx::query!(
+            r#"
+                INSERT INTO "api_key" (id, key_hash, key_prefix, secret, user_id, label, scopes, allowed_ips, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            api_key.id,
+            api_key.key_hash,
+            api_key.key_prefix,
+            api_key.secret,
+            api_key.user_id,
+            api_key.label,
+            &api_key.scopes,
+            api_key.allowed_ips.as_deref(),
+            api_key.created_at,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_api_key_by_hash(key_hash:&str, pool:&Pool<Postgres>) -> Result<Option<ApiKey>, Error>{
+
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+                SELECT id, key_hash, key_prefix, secret, user_id, label, scopes, allowed_ips, created_at
+                FROM "api_key"
+                WHERE key_hash = $1
+            "#,
+            key_hash,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    pub async fn get_api_key(id:&str, user_id:&str, pool:&Pool<Postgres>) -> Result<Option<ApiKey>, Error>{
+
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+                SELECT id, key_hash, key_prefix, secret, user_id, label, scopes, allowed_ips, created_at
+                FROM "api_key"
+                WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user_id,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    /// keys of the user, oldest first
+    pub async fn get_user_api_keys(user_id:&str, pool:&Pool<Postgres>) -> Result<Vec<ApiKey>, Error>{
+
+        let api_keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+                SELECT id, key_hash, key_prefix, secret, user_id, label, scopes, allowed_ips, created_at
+                FROM "api_key"
+                WHERE user_id = $1
+                ORDER BY created_at, id
+            "#,
+            user_id,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(api_keys)
+    }
+
+    /// false if the user has no key with the id
+    pub async fn delete_api_key(id:&str, user_id:&str, pool:&Pool<Postgres>) -> Result<bool, Error>{
+
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM "api_key"
+                WHERE id = $1 AND user_id = $2
+            "#,
+            id,
+            user_id,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, Pool, Postgres};
+
+/// A trade from the side of one of its users,
+/// a user trading with itself has both the maker and the taker fill
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Fill {
+    pub trade_id: i64,
+    pub market: String,
+    pub order_id: String,
+    pub side: String,
+    // Maker or Taker
+    pub role: String,
+    pub price: String,
+    pub quantity: String,
+    pub quote_qty: String,
+    pub fee: String,
+    pub fee_asset: String,
+    pub matched_at: i64,
+}
+
+/// Filters of the fills of a user, times are in milliseconds
+pub struct FillFilter {
+    pub user_id: String,
+    pub market: Option<String>,
+    pub side: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub cursor: Option<FillCursor>,
+    pub limit: i64,
+}
+
+/// Position of the last fill of a page, the fills are sorted by the latest first
+pub struct FillCursor {
+    pub matched_at: i64,
+    pub market: String,
+    pub trade_id: i64,
+    pub role: String,
+}
+
+impl FillCursor {
+    pub fn encode(&self) -> String {
+        format!("{}:{}:{}:{}", self.matched_at, self.market, self.trade_id, self.role)
+    }
+
+    pub fn decode(cursor:&str) -> Option<Self> {
+        let mut parts = cursor.split(':');
+
+        let cursor = Self {
+            matched_at: parts.next()?.parse().ok()?,
+            market: parts.next()?.to_string(),
+            trade_id: parts.next()?.parse().ok()?,
+            role: parts.next()?.to_string(),
+        };
+
+        match parts.next() {
+            Some(_) => None,
+            None => Some(cursor),
+        }
+    }
+}
+
+impl Fill {
+
+    /// fills of the user matching the filter, latest first.
+    /// returns the cursor of the next page if there are more fills
+    pub async fn get_fills(filter:FillFilter, pool:&Pool<Postgres>) -> Result<(Vec<Fill>, Option<FillCursor>), Error>{
+
+        let (cursor_matched_at, cursor_market, cursor_trade_id, cursor_role) = match filter.cursor {
+            Some(cursor) => (Some(cursor.matched_at), Some(cursor.market), Some(cursor.trade_id), Some(cursor.role)),
+            None => (None, None, None, None),
+        };
+
+        // one more than the limit, to know if there is a next page
+        let mut fills = sqlx::query_as!(
+            Fill,
+            r#"
+                SELECT trade_id AS "trade_id!", market AS "market!", order_id AS "order_id!", side AS "side!", role AS "role!",
+                    price AS "price!", quantity AS "quantity!", quote_qty AS "quote_qty!", fee AS "fee!", fee_asset AS "fee_asset!",
+                    matched_at AS "matched_at!"
+                FROM (
+                    SELECT id AS trade_id, market, maker_order_id AS order_id,
+                        CASE WHEN is_buyer_maker THEN 'Buy' ELSE 'Sell' END AS side, 'Maker' AS role,
+                        price, quantity, quote_qty, maker_fee AS fee, maker_fee_asset AS fee_asset, matched_at
+                    FROM "trade" WHERE maker_user_id = $1
+                    UNION ALL
+                    SELECT id, market, taker_order_id,
+                        CASE WHEN is_buyer_maker THEN 'Sell' ELSE 'Buy' END, 'Taker',
+                        price, quantity, quote_qty, taker_fee, taker_fee_asset, matched_at
+                    FROM "trade" WHERE taker_user_id = $1
+                ) AS fills
+                WHERE ($2::varchar IS NULL OR market = $2)
+                AND ($3::varchar IS NULL OR side = $3)
+                AND ($4::bigint IS NULL OR matched_at >= $4)
+                AND ($5::bigint IS NULL OR matched_at <= $5)
+                AND ($6::bigint IS NULL OR (matched_at, market, trade_id, role) < ($6, $7::varchar, $8::bigint, $9::varchar))
+                ORDER BY matched_at DESC, market DESC, trade_id DESC, role DESC
+                LIMIT $10
+            "#,
+            filter.user_id,
+            filter.market,
+            filter.side,
+            filter.start_time,
+            filter.end_time,
+            cursor_matched_at,
+            cursor_market,
+            cursor_trade_id,
+            cursor_role,
+            filter.limit + 1,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut next_cursor = None;
+
+        if fills.len() as i64 > filter.limit {
+            fills.truncate(filter.limit as usize);
+
+            next_cursor = fills.last().map(|fill| FillCursor {
+                matched_at: fill.matched_at,
+                market: fill.market.clone(),
+                trade_id: fill.trade_id,
+                role: fill.role.clone(),
+            });
+        }
+
+        Ok((fills, next_cursor))
+    }
+}
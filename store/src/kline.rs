@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, Pool, Postgres};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Kline {
+    pub market: String,
+    pub interval: String,
+    pub open_time: i64,
+    pub close_time: i64,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+    pub quote_volume: String,
+    pub trade_count: i64,
+}
+
+/// Filters of the klines of a market, times are in milliseconds
+pub struct KlineFilter {
+    pub market: String,
+    pub interval: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: i64,
+}
+
+impl Kline {
+
+    /// the kline is aggregated by the db filler, so the stored kline is replaced
+    pub async fn upsert_kline(kline:Kline, pool:&Pool<Postgres>) -> Result<(), Error>{
+
+        sqlx::query!(
+            r#"
+                INSERT INTO "kline" (market, "interval", open_time, close_time, open, high, low, close, volume, quote_volume, trade_count)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                ON CONFLICT (market, "interval", open_time) DO UPDATE
+                SET high = EXCLUDED.high, low = EXCLUDED.low, close = EXCLUDED.close,
+                    volume = EXCLUDED.volume, quote_volume = EXCLUDED.quote_volume, trade_count = EXCLUDED.trade_count
+            "#,
+            kline.market,
+            kline.interval,
+            kline.open_time,
+            kline.close_time,
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            kline.volume,
+            kline.quote_volume,
+            kline.trade_count,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_kline(market:&str, interval:&str, open_time:i64, pool:&Pool<Postgres>) -> Result<Option<Kline>, Error>{
+
+        sqlx::query_as!(
+            Kline,
+            r#"
+                SELECT market, "interval", open_time, close_time, open, high, low, close, volume, quote_volume, trade_count
+                FROM "kline"
+                WHERE market = $1 AND "interval" = $2 AND open_time = $3
+            "#,
+            market,
+            interval,
+            open_time,
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// latest klines of the market matching the filter, oldest first
+    pub async fn get_klines(filter:KlineFilter, pool:&Pool<Postgres>) -> Result<Vec<Kline>, Error>{
+
+        let mut klines = sqlx::query_as!(
+            Kline,
+            r#"
+                SELECT market, "interval", open_time, close_time, open, high, low, close, volume, quote_volume, trade_count
+                FROM "kline"
+                WHERE market = $1 AND "interval" = $2
+                AND ($3::bigint IS NULL OR open_time >= $3)
+                AND ($4::bigint IS NULL OR open_time <= $4)
+                ORDER BY open_time DESC
+                LIMIT $5
+            "#,
+            filter.market,
+            filter.interval,
+            filter.start_time,
+            filter.end_time,
+            filter.limit,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        klines.reverse();
+
+        Ok(klines)
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, Pool, Postgres};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub user_id: String,
+    pub asset: String,
+    pub amount: String,
+    pub entry_type: String,
+    pub created_at: i64,
+}
+
+impl LedgerEntry {
+
+    /// the id is the journal sequence of the movement, so an entry
+    /// published again after a restart of the engine is ignored
+    pub async fn add_entry(entry:LedgerEntry, pool:&Pool<Postgres>) -> Result<(), Error>{
+
+        sqlx::query!(
+            r#"
+                INSERT INTO "ledger" (id, user_id, asset, amount, entry_type, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (id) DO NOTHING
+            "#,
+            entry.id,
+            entry.user_id,
+            entry.asset,
+            entry.amount,
+            entry.entry_type,
+            entry.created_at,
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,13 @@
+mod api_key;
+mod fill;
+mod kline;
+mod ledger;
 mod order;
 mod trade;
 
+pub use api_key::*;
+pub use fill::*;
+pub use kline::*;
+pub use ledger::*;
 pub use order::*;
-pub use trade::*;
\ No newline at end of file
+pub use trade::*;
@@ -9,16 +9,58 @@ pub struct Order{
     pub price: String,
     pub side: String,
     pub order_status: String,
+    pub user_id: String,
+    pub market: String,
+    pub order_type: String,
+    // milliseconds, set from the time the engine accepted the message
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Filters of the order history of a user, times are in milliseconds
+pub struct OrderFilter {
+    pub user_id: String,
+    pub market: Option<String>,
+    pub status: Option<String>,
+    pub side: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub cursor: Option<OrderCursor>,
+    pub limit: i64,
+}
+
+/// Position of the last order of a page, the orders are sorted by the latest first
+pub struct OrderCursor {
+    pub created_at: i64,
+    pub id: String,
+}
+
+impl OrderCursor {
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.created_at, self.id)
+    }
+
+    pub fn decode(cursor:&str) -> Option<Self> {
+        let (created_at, id) = cursor.split_once(':')?;
+
+        Some(Self {
+            created_at: created_at.parse().ok()?,
+            id: id.to_string(),
+        })
+    }
 }
 
 pub struct UpdateDbOrder{
     pub order_id: String,
     pub filled_quantity: String,
     pub status: String,
+    pub updated_at: i64,
 }
 
 impl Order {
 
+    /// an order published again after a restart of the engine is already stored, it is set
+    /// back to its state at that point and the updates published after it follow in order
     pub async fn add_order(
         order:Order, 
         pool:&Pool<Postgres>
@@ -27,9 +69,11 @@ impl Order {
         let order =  sqlx::query_as!(
             Order,
             r#"
-                INSERT INTO "order" (id, quantity, filled_quantity, price, side, order_status)
-                VALUES ($1, $2, $3, $4, $5, $6)
-                RETURNING *
+                INSERT INTO "order" (id, quantity, filled_quantity, price, side, order_status, user_id, market, order_type, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                ON CONFLICT (id) DO UPDATE
+                SET filled_quantity = EXCLUDED.filled_quantity, order_status = EXCLUDED.order_status, updated_at = EXCLUDED.updated_at
+                RETURNING id, quantity, filled_quantity, price, side, order_status, user_id, market, order_type, created_at, updated_at
             "#,
             order.id,
             order.quantity,
@@ -37,6 +81,11 @@ impl Order {
             order.price,
             order.side,
             order.order_status,
+            order.user_id,
+            order.market,
+            order.order_type,
+            order.created_at,
+            order.updated_at,
         )
         .fetch_one(pool)
         .await?;
@@ -47,7 +96,7 @@ impl Order {
 
     pub async fn update_orders(orders:Vec<UpdateDbOrder>, pool:&Pool<Postgres>) -> Result<(), Error>{
 
-        if orders.len() == 0{
+        if orders.is_empty(){
             return Ok(());
         }
 
@@ -58,11 +107,12 @@ impl Order {
             let res =  sqlx::query!(
                 r#"
                     UPDATE "order"
-                    SET filled_quantity = $1, order_status = $2
-                    WHERE id = $3;  
+                    SET filled_quantity = $1, order_status = $2, updated_at = $3
+                    WHERE id = $4;  
                 "#,
                 order.filled_quantity,
                 order.status,
+                order.updated_at,
                 order.order_id
             )
             .execute(pool);
@@ -78,18 +128,23 @@ impl Order {
 
     }
 
-    pub async fn update_cancelled_orders(orders:Vec<String>, pool:&Pool<Postgres>) -> Result<(), Error>{
+    /// cancels the orders of the user that are still open or pending,
+    /// filled and already cancelled orders are left as they are
+    pub async fn update_cancelled_orders(user_id:&str, orders:Vec<String>, updated_at:i64, pool:&Pool<Postgres>) -> Result<(), Error>{
 
-        if orders.len() == 0 {
+        if orders.is_empty() {
             println!("no orders to cancel !");
             return Ok(());
         }
 
         let mut query_builder: sqlx::QueryBuilder<'_, Postgres> = sqlx::QueryBuilder::new(r#"
             UPDATE "order"
-            SET order_status = 'Cancelled'
-            WHERE id in (
-        "#);
+            SET order_status = 'Cancelled', updated_at = "#);
+
+        query_builder.push_bind(updated_at);
+        query_builder.push(" WHERE user_id = ");
+        query_builder.push_bind(user_id.to_string());
+        query_builder.push(" AND order_status IN ('Open', 'Pending') AND id in (");
 
         let mut separated = query_builder.separated(", ");
 
@@ -104,4 +159,56 @@ impl Order {
         Ok(())
     }
 
-}
\ No newline at end of file
+    /// orders of the user matching the filter, latest first.
+    /// returns the cursor of the next page if there are more orders
+    pub async fn get_orders(filter:OrderFilter, pool:&Pool<Postgres>) -> Result<(Vec<Order>, Option<OrderCursor>), Error>{
+
+        let (cursor_created_at, cursor_id) = match filter.cursor {
+            Some(cursor) => (Some(cursor.created_at), Some(cursor.id)),
+            None => (None, None),
+        };
+
+        // one more than the limit, to know if there is a next page
+        let mut orders = sqlx::query_as!(
+            Order,
+            r#"
+                SELECT id, quantity, filled_quantity, price, side, order_status, user_id, market, order_type, created_at, updated_at
+                FROM "order"
+                WHERE user_id = $1
+                AND ($2::varchar IS NULL OR market = $2)
+                AND ($3::varchar IS NULL OR order_status = $3)
+                AND ($4::varchar IS NULL OR side = $4)
+                AND ($5::bigint IS NULL OR created_at >= $5)
+                AND ($6::bigint IS NULL OR created_at <= $6)
+                AND ($7::bigint IS NULL OR (created_at, id) < ($7, $8::varchar))
+                ORDER BY created_at DESC, id DESC
+                LIMIT $9
+            "#,
+            filter.user_id,
+            filter.market,
+            filter.status,
+            filter.side,
+            filter.start_time,
+            filter.end_time,
+            cursor_created_at,
+            cursor_id,
+            filter.limit + 1,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut next_cursor = None;
+
+        if orders.len() as i64 > filter.limit {
+            orders.truncate(filter.limit as usize);
+
+            next_cursor = orders.last().map(|order| OrderCursor {
+                created_at: order.created_at,
+                id: order.id.clone(),
+            });
+        }
+
+        Ok((orders, next_cursor))
+    }
+
+}
@@ -9,55 +9,134 @@ pub struct Trade {
     pub quantity: String,
     pub quote_qty: String,
     pub matched_at: i64,
+    pub maker_fee: String,
+    pub maker_fee_asset: String,
+    pub taker_fee: String,
+    pub taker_fee_asset: String,
+    pub maker_order_id: String,
+    pub taker_order_id: String,
+    pub maker_user_id: String,
+    pub taker_user_id: String,
+    pub is_buyer_maker: bool,
 }
 
+/// Trade as shown on the public trade history, without the users and orders
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MarketTrade {
+    pub id: i64,
+    pub market: String,
+    pub price: String,
+    pub quantity: String,
+    pub quote_qty: String,
+    pub matched_at: i64,
+    pub is_buyer_maker: bool,
+}
+
+/// Filters of the trade history of a market, times are in milliseconds
+pub struct MarketTradeFilter {
+    pub market: String,
+    pub from_id: Option<i64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: i64,
+}
 
 impl Trade {
 
-    pub async fn add_trades(trades:Vec<Trade>, pool:&Pool<Postgres>) -> Result<(), Error>{
+    /// returns the market and id of the trades which were not stored before
+    pub async fn add_trades(trades:Vec<Trade>, pool:&Pool<Postgres>) -> Result<Vec<(String, i64)>, Error>{
 
         // NOTE: QUERY BUILDER WILL THROW ERROR IF THE TRADES ARE EMPTY
         // SO RETURN EALRY IF TRADES IS EMPTY
 
-        if trades.len() == 0{
-            return Ok(());
+        if trades.is_empty(){
+            return Ok(vec![]);
         }
 
         let mut query_builder: sqlx::QueryBuilder<'_, Postgres> = sqlx::QueryBuilder::new(r#"
-            INSERT INTO "trade" (id, market, price, quantity, quote_qty, matched_at)
+            INSERT INTO "trade" (id, market, price, quantity, quote_qty, matched_at, maker_fee, maker_fee_asset, taker_fee, taker_fee_asset,
+                maker_order_id, taker_order_id, maker_user_id, taker_user_id, is_buyer_maker)
         "#);
 
         query_builder.push_values(trades, |mut b, trade|{
-            
-            // convert to i64
-            let id = i64::from(trade.id);
-            let matched_at = i64::from(trade.matched_at);
 
-            b.push_bind(id);
+            b.push_bind(trade.id);
             b.push_bind(trade.market);
             b.push_bind(trade.price);
             b.push_bind(trade.quantity);
             b.push_bind(trade.quote_qty);
-            b.push_bind(matched_at);
+            b.push_bind(trade.matched_at);
+            b.push_bind(trade.maker_fee);
+            b.push_bind(trade.maker_fee_asset);
+            b.push_bind(trade.taker_fee);
+            b.push_bind(trade.taker_fee_asset);
+            b.push_bind(trade.maker_order_id);
+            b.push_bind(trade.taker_order_id);
+            b.push_bind(trade.maker_user_id);
+            b.push_bind(trade.taker_user_id);
+            b.push_bind(trade.is_buyer_maker);
         });
+
+        // the engine publishes the trades of the journal entries it recovers on startup again,
+        // the ones which reached the db before the crash are skipped
+        query_builder.push(" ON CONFLICT (market, id) DO NOTHING RETURNING market, id");
         
-        query_builder.build().execute(pool).await?;
+        let added = query_builder.build_query_as::<(String, i64)>().fetch_all(pool).await?;
 
-        Ok(())
+        Ok(added)
     }
 
-    pub async fn get_trades(pool:&Pool<Postgres>) -> Result<Vec<Trade>, Error>{
-        
-        let trades = sqlx::query_as!(
-            Trade,
-            r#"
-                SELECT * FROM "trade" ORDER BY matched_at DESC;
-            "#
-        )
-        .fetch_all(pool)
-        .await;
-
-        trades
+    /// trades of the market matching the filter, oldest first. starts from the trade `from_id`
+    /// when given, else returns the latest trades
+    pub async fn get_market_trades(filter:MarketTradeFilter, pool:&Pool<Postgres>) -> Result<Vec<MarketTrade>, Error>{
+
+        match filter.from_id {
+            Some(from_id) => {
+                sqlx::query_as!(
+                    MarketTrade,
+                    r#"
+                        SELECT id, market, price, quantity, quote_qty, matched_at, is_buyer_maker
+                        FROM "trade"
+                        WHERE market = $1 AND id >= $2
+                        AND ($3::bigint IS NULL OR matched_at >= $3)
+                        AND ($4::bigint IS NULL OR matched_at <= $4)
+                        ORDER BY id ASC
+                        LIMIT $5
+                    "#,
+                    filter.market,
+                    from_id,
+                    filter.start_time,
+                    filter.end_time,
+                    filter.limit,
+                )
+                .fetch_all(pool)
+                .await
+            },
+            None => {
+                let mut trades = sqlx::query_as!(
+                    MarketTrade,
+                    r#"
+                        SELECT id, market, price, quantity, quote_qty, matched_at, is_buyer_maker
+                        FROM "trade"
+                        WHERE market = $1
+                        AND ($2::bigint IS NULL OR matched_at >= $2)
+                        AND ($3::bigint IS NULL OR matched_at <= $3)
+                        ORDER BY id DESC
+                        LIMIT $4
+                    "#,
+                    filter.market,
+                    filter.start_time,
+                    filter.end_time,
+                    filter.limit,
+                )
+                .fetch_all(pool)
+                .await?;
+
+                trades.reverse();
+
+                Ok(trades)
+            }
+        }
     }
 
 
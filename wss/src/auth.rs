@@ -0,0 +1,108 @@
+use std::{net::IpAddr, sync::Arc, time::{SystemTime, UNIX_EPOCH}};
+
+use common::{auth::{hash_api_key, is_in_recv_window, is_ip_allowed, verify_signature, ApiKeyScope, DEFAULT_RECV_WINDOW, MAX_CLOCK_DRIFT, MAX_RECV_WINDOW}, channel::{KLINE_WS_STREAM_PREFIX, PRIVATE_WS_STREAMS, PUBLIC_WS_STREAMS}};
+use store::ApiKey;
+use tokio::sync::Mutex;
+
+use crate::{AppState, ClientMessage, RedisManager};
+
+/// Verifies the login message of a client and returns the user of its api key.
+/// The signature is the hex encoded HMAC-SHA256 of `timestamp + "LOGIN"` with the secret of the key,
+/// and is accepted only within the recv window and only once, same as the signed api requests.
+pub async fn login(
+    message: &ClientMessage,
+    ip: IpAddr,
+    app_state: &Arc<Mutex<AppState>>
+) -> Result<String, &'static str> {
+
+    let (api_key, timestamp, signature) = match (
+        &message.api_key,
+        message.timestamp,
+        message.signature.as_ref().and_then(|signature| hex::decode(signature).ok()),
+    ) {
+        (Some(api_key), Some(timestamp), Some(signature)) => (api_key, timestamp, signature),
+        _ => return Err("api_key, timestamp and signature are required"),
+    };
+
+    let recv_window = match message.recv_window {
+        Some(recv_window) if recv_window > 0 && recv_window <= MAX_RECV_WINDOW => recv_window,
+        Some(_) => return Err("recv window must be between 1 and 60000 ms"),
+        None => DEFAULT_RECV_WINDOW,
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis() as i64);
+
+    if !is_in_recv_window(timestamp, recv_window, now) {
+        println!("login of api key : {} with timestamp : {} is outside the recv window at : {}", api_key, timestamp, now);
+        return Err("timestamp is outside the recv window");
+    }
+
+    // the state is not held while the key is fetched
+    let guard = app_state.lock().await;
+    let db_pool = guard.db_pool.clone();
+    let mut redis = guard.redis.clone_conn();
+    drop(guard);
+
+    let key = match ApiKey::get_api_key_by_hash(&hash_api_key(api_key), &db_pool).await {
+        Ok(Some(key)) => key,
+        Ok(None) => return Err("invalid api key"),
+        Err(e) => {
+            println!("error : {} while fetching api key", e);
+            return Err("internal error");
+        }
+    };
+
+    if !is_ip_allowed(&key.allowed_ips, Some(ip)) {
+        println!("login of api key : {} from ip : {} which is not allowed", key.id, ip);
+        return Err("ip address is not allowed for this api key");
+    }
+
+    let payload = format!("{}LOGIN", timestamp);
+
+    if !verify_signature(&key.secret, payload.as_bytes(), &signature) {
+        println!("invalid login signature for api key : {}", key.id);
+        return Err("invalid signature");
+    }
+
+    if !key.scopes.iter().any(|scope| scope == ApiKeyScope::Read.as_str()) {
+        return Err("api key doesn't have the read scope");
+    }
+
+    match RedisManager::save_signature(&mut redis, &hex::encode(&signature), recv_window + MAX_CLOCK_DRIFT).await {
+        Ok(true) => {},
+        Ok(false) => {
+            println!("replayed login of api key : {}", key.id);
+            return Err("login was already received");
+        },
+        Err(e) => {
+            println!("error : {} while saving login signature", e);
+            return Err("internal error");
+        }
+    }
+
+    Ok(key.user_id)
+}
+
+/// public channels can be subscribed by anyone, the private
+/// channels of a user only by the user after the login
+pub fn check_channel(channel: &str, user_id: Option<&str>) -> Result<(), &'static str> {
+
+    let (stream, target) = match channel.split_once('@') {
+        Some(stream_and_target) => stream_and_target,
+        None => return Err("invalid channel"),
+    };
+
+    if PRIVATE_WS_STREAMS.contains(&stream) {
+        return match user_id {
+            Some(user_id) if user_id == target => Ok(()),
+            Some(_) => Err("private channels of other users can't be subscribed"),
+            None => Err("login to subscribe to private channels"),
+        };
+    }
+
+    if PUBLIC_WS_STREAMS.contains(&stream) || stream.starts_with(KLINE_WS_STREAM_PREFIX) {
+        return Ok(());
+    }
+
+    Err("invalid channel")
+}
@@ -1,29 +1,40 @@
 use std::{net::SocketAddr, sync::{Arc}};
+use common::message::ws::WsMessage;
 use futures_util::{pin_mut, SinkExt, StreamExt};
 use serde::Deserialize;
+use sqlx::{Pool, Postgres};
 use tokio::{net::TcpStream, sync::{mpsc, Mutex}};
 pub use redis::RedisManager;
 use tokio_tungstenite::tungstenite::Message;
 pub use user::UserManager;
 
+mod auth;
 mod redis;
 mod user;
 
 pub struct AppState {
     pub user_manager : UserManager,
     pub redis: RedisManager,
+    // api keys of the logins
+    pub db_pool: Pool<Postgres>,
 }
 
 impl AppState {
-    pub fn new(user_manager:UserManager, redis:RedisManager) -> Self{
-        Self { user_manager, redis }
+    pub fn new(user_manager:UserManager, redis:RedisManager, db_pool:Pool<Postgres>) -> Self{
+        Self { user_manager, redis, db_pool }
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ClientMessage {
     pub message_type:String,
+    #[serde(default)]
     pub channel: String, 
+    // signed LOGIN message
+    pub api_key: Option<String>,
+    pub timestamp: Option<i64>,
+    pub recv_window: Option<i64>,
+    pub signature: Option<String>,
 }
 
 /// Client's inbound and outbound
@@ -49,7 +60,9 @@ pub async fn handle_connection(
     let (tx, mut rx) = mpsc::unbounded_channel::<String>();    
 
     let mut guard = app_state.lock().await;
-    let user_id = socket_addr.port().to_string();
+
+    // the port alone is not unique across the clients
+    let user_id = socket_addr.to_string();
 
     println!("user connected : {}", user_id);
 
@@ -60,22 +73,58 @@ pub async fn handle_connection(
 
     let read_fut = async {
 
+        // user of the api key the client logged in with
+        let mut logged_in_user: Option<String> = None;
+
         while let Some(try_message) = stream.next().await {
 
             match try_message {
                 Ok(message) => {                    
 
                     let str_message = message.to_string();                
-                    let mut guard = app_state.lock().await;
                     let try_deserialized: Result<ClientMessage, serde_json::Error> = serde_json::from_str(&str_message);
 
                     match try_deserialized{
                         Ok(client_message) => {
                 
-                            if client_message.message_type == "SUBSCRIBE" {
+                            if client_message.message_type == "LOGIN" {
+
+                                let message = match &logged_in_user {
+                                    Some(_) => String::from("Login failed : already logged in"),
+                                    None => match auth::login(&client_message, socket_addr.ip(), &app_state).await {
+                                        Ok(logged_in_user_id) => {
+                                            println!("{} logged in as user : {}", user_id, logged_in_user_id);
+                                            logged_in_user = Some(logged_in_user_id.clone());
+                                            serde_json::to_string(&WsMessage::Login { user_id: logged_in_user_id }).unwrap_or_default()
+                                        },
+                                        Err(e) => {
+                                            println!("login failed for : {} , error : {}", user_id, e);
+                                            format!("Login failed : {}", e)
+                                        }
+                                    }
+                                };
+
+                                if let Err(e) = tx.send(message) {
+                                    println!("error : {} while sending message to rx", e);
+                                }
+                            }
+                            else if client_message.message_type == "SUBSCRIBE" {
 
                                 let channel = client_message.channel;
 
+                                if let Err(e) = auth::check_channel(&channel, logged_in_user.as_deref()) {
+
+                                    println!("{} can't subscribe to the channel : {} , error : {}", user_id, channel, e);
+
+                                    let message = format!("Subscribe failed : {}", e);
+                                    if let Err(e) = tx.send(message) {
+                                        println!("error : {} while sending message to rx", e);
+                                    }
+                                    continue;
+                                }
+
+                                let mut guard = app_state.lock().await;
+
                                 let user_manager = &mut guard.user_manager;
                                 user_manager.subscribe(user_id.clone(), channel.clone()).await;
 
@@ -90,6 +139,8 @@ pub async fn handle_connection(
                                
                                 let channel = client_message.channel;
 
+                                let mut guard = app_state.lock().await;
+
                                 let user_manager = &mut guard.user_manager;
                                 user_manager.unsubscribe(user_id.clone(), channel.clone()).await;
 
@@ -35,6 +35,28 @@ impl RedisManager {
         }, rx)
     }
 
+    /// connection for the commands which don't need the state, the multiplexed connection is shared
+    pub fn clone_conn(&self) -> MultiplexedConnection {
+        self.conn.clone()
+    }
+
+    /// false if the signature was already saved, it is kept for the expiry so a login can't be replayed
+    pub async fn save_signature(conn:&mut MultiplexedConnection, signature:&str, expiry_ms:i64) -> Result<bool, RedisError>{
+
+        let key = format!("signature:{}", signature);
+
+        let res: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(1)
+        .arg("NX")
+        .arg("PX")
+        .arg(expiry_ms)
+        .query_async(conn)
+        .await?;
+
+        Ok(res.is_some())
+    }
+
     pub async fn subscribe(&mut self, channel:&str, user_id:String) -> Result<(), RedisError>{
         
         // check if there is a channel exists and subscribe
@@ -72,7 +94,7 @@ impl RedisManager {
             users.remove(user_id);
 
             // if no one is present on the channel then unsubscribe from the channel
-            if users.len() == 0 {
+            if users.is_empty() {
                 println!("unsunscribing from redis channel : {}", channel);
                 self.conn.unsubscribe(channel).await?;
                 self.channels_and_users.remove(channel);
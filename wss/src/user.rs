@@ -6,6 +6,12 @@ pub struct UserManager {
     user_subscribed_channels: HashMap<String, HashSet<String>>
 }
 
+impl Default for UserManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserManager {
 
     pub fn new() -> Self  {